@@ -76,6 +76,38 @@ pub fn build() -> App<'static, 'static> {
                 .value_name("path")
                 .multiple(true),
         )
+        .arg(
+            arg("exclude-from")
+                .long("exclude-from")
+                .number_of_values(1)
+                .value_name("file")
+                .multiple(true)
+                .hidden_short_help(true),
+        )
+        .arg(
+            arg("files-from")
+                .long("files-from")
+                .takes_value(true)
+                .value_name("file")
+                .conflicts_with_all(&["include", "ordered"])
+                .hidden_short_help(true),
+        )
+        .arg(
+            arg("exclude-glob")
+                .long("exclude-glob")
+                .number_of_values(1)
+                .value_name("pattern")
+                .multiple(true)
+                .hidden_short_help(true),
+        )
+        .arg(
+            arg("exclude-regex")
+                .long("exclude-regex")
+                .number_of_values(1)
+                .value_name("pattern")
+                .multiple(true)
+                .hidden_short_help(true),
+        )
         .arg(
             arg("use-glob")
                 .long("glob")
@@ -116,6 +148,7 @@ pub fn build() -> App<'static, 'static> {
                 .short("L")
                 .hidden_short_help(true),
         )
+        .arg(arg("unique").long("unique").hidden_short_help(true))
         .arg(
             arg("same-file-system")
                 .long("mount")
@@ -134,20 +167,161 @@ pub fn build() -> App<'static, 'static> {
                 .short("A")
                 .hidden_short_help(true),
         )
+        .arg(
+            arg("real-path")
+                .long("real-path")
+                .overrides_with("logical-path")
+                .hidden_short_help(true),
+        )
+        .arg(
+            arg("logical-path")
+                .long("logical-path")
+                .overrides_with("real-path")
+                .hidden_short_help(true),
+        )
         .arg(
             arg("sort-path")
                 .long("sort-path")
                 .short("S")
                 .hidden_short_help(true),
         )
+        .arg(
+            arg("sort")
+                .long("sort")
+                .takes_value(true)
+                .use_delimiter(true)
+                .value_delimiter(",")
+                .value_name("key")
+                .hidden_short_help(true),
+        )
+        .arg(arg("reverse").long("reverse").hidden_short_help(true))
+        .arg(
+            arg("ordered")
+                .long("ordered")
+                .conflicts_with_all(&["sort-path", "sort", "reverse"])
+                .hidden_short_help(true),
+        )
+        .arg(
+            arg("sort-memory")
+                .long("sort-memory")
+                .takes_value(true)
+                .value_name("size")
+                .hidden_short_help(true),
+        )
         .arg(arg("dot-files").long("all").short("a"))
         .arg(arg("no-ignore").long("no-ignore").short("I"))
+        .arg(
+            arg("no-ignore-vcs")
+                .long("no-ignore-vcs")
+                .hidden_short_help(true),
+        )
+        .arg(
+            arg("no-ignore-parent")
+                .long("no-ignore-parent")
+                .hidden_short_help(true),
+        )
+        .arg(
+            arg("no-global-ignore")
+                .long("no-global-ignore")
+                .hidden_short_help(true),
+        )
+        .arg(
+            arg("ignore-file")
+                .long("ignore-file")
+                .number_of_values(1)
+                .value_name("path")
+                .multiple(true)
+                .hidden_short_help(true),
+        )
         .arg(
             arg("multiplex")
                 .long("multiplex")
                 .short("m")
                 .hidden_short_help(true),
         )
+        .arg(
+            arg("strip-prefix")
+                .long("strip-prefix")
+                .overrides_with_all(&["base-name", "relative-to"])
+                .conflicts_with("tree")
+                .hidden_short_help(true),
+        )
+        .arg(
+            arg("relative-to")
+                .long("relative-to")
+                .takes_value(true)
+                .value_name("path")
+                .overrides_with_all(&["strip-prefix", "base-name"])
+                .conflicts_with_all(&["tree", "absolute-path"])
+                .hidden_short_help(true),
+        )
+        .arg(
+            arg("base-name")
+                .long("base-name")
+                .overrides_with_all(&["strip-prefix", "relative-to"])
+                .conflicts_with("tree")
+                .hidden_short_help(true),
+        )
+        .arg(
+            arg("output-format")
+                .long("output-format")
+                .takes_value(true)
+                .value_name("format")
+                .possible_values(&["plain", "json", "csv", "tsv", "long"])
+                .overrides_with_all(&["json", "long"])
+                .conflicts_with("exec")
+                .hidden_short_help(true),
+        )
+        .arg(
+            arg("json")
+                .long("json")
+                .overrides_with_all(&["output-format", "long"])
+                .conflicts_with("exec")
+                .hidden_short_help(true),
+        )
+        .arg(
+            arg("long")
+                .long("long")
+                .short("l")
+                .overrides_with_all(&["output-format", "json"])
+                .conflicts_with("exec")
+                .hidden_short_help(true),
+        )
+        .arg(
+            arg("tree")
+                .long("tree")
+                .takes_value(true)
+                .min_values(0)
+                .require_equals(true)
+                .value_name("style")
+                .possible_values(&["unicode", "ascii"])
+                .conflicts_with_all(&[
+                    "output-format",
+                    "json",
+                    "long",
+                    "exec",
+                    "null-terminator",
+                    "sort",
+                    "reverse",
+                ])
+                .hidden_short_help(true),
+        )
+        .arg(
+            arg("columns")
+                .long("columns")
+                .takes_value(true)
+                .use_delimiter(true)
+                .value_delimiter(",")
+                .value_name("column")
+                .hidden_short_help(true),
+        )
+        .arg(
+            arg("count")
+                .long("count")
+                .conflicts_with_all(&["output-format", "json", "long", "tree", "exec"])
+                .hidden_short_help(true),
+        )
+        .arg(arg("stats").long("stats").hidden_short_help(true))
         .arg(
             arg("file-type")
                 .long("type")
@@ -166,6 +340,20 @@ pub fn build() -> App<'static, 'static> {
                 .value_name("number")
                 .hidden_short_help(true),
         )
+        .arg(
+            arg("max-results")
+                .long("max-results")
+                .takes_value(true)
+                .value_name("number")
+                .overrides_with("one")
+                .hidden_short_help(true),
+        )
+        .arg(
+            arg("one")
+                .short("1")
+                .overrides_with("max-results")
+                .hidden_short_help(true),
+        )
         .arg(
             arg("color")
                 .long("color")
@@ -183,6 +371,13 @@ pub fn build() -> App<'static, 'static> {
                 .value_name("number")
                 .hidden_short_help(true),
         )
+        .arg(
+            arg("sniff-size")
+                .long("sniff-size")
+                .takes_value(true)
+                .value_name("size")
+                .hidden_short_help(true),
+        )
         .arg(
             arg("max-buffer-time")
                 .long("max-buffer-time")
@@ -227,7 +422,7 @@ fn get_help() -> HashMap<&'static str, Help> {
         "Search one more directory or file.\n\
          \n\
          This option can be specified multiple times. \
-         Duplicated paths produce duplicated results."
+         Duplicated paths produce duplicated results unless --unique is used."
     );
 
     doc!(
@@ -240,6 +435,62 @@ fn get_help() -> HashMap<&'static str, Help> {
          File paths are compared without resorting to absolute paths nor real paths."
     );
 
+    doc!(
+        help,
+        "exclude-from",
+        "Remove branches listed in a file.",
+        "Skip the files or do not descend into the directories listed in the file, \
+         like --exclude. Use \"-\" to read the list from stdin.\n\
+         \n\
+         The paths are delimited by NUL if there is any, otherwise by newline. \
+         This option can be specified multiple times."
+    );
+
+    doc!(
+        help,
+        "files-from",
+        "Test the paths listed in a file instead.",
+        "Test each path listed in the file against the filter chain \
+         without walking the directories, e.g. the output of `git ls-files -z`. \
+         Use \"-\" to read the list from stdin.\n\
+         \n\
+         The paths are delimited by NUL if there is any, otherwise by newline. \
+         Relative paths are relative to the current working directory, \
+         and no other starting point can be used. \
+         Paths under excluded directories are skipped, \
+         but the action \"prune\" only skips the path itself."
+    );
+
+    doc!(
+        help,
+        "exclude-glob",
+        "Remove branches matching a glob pattern.",
+        "Skip the files or do not descend into the directories \
+         of which the paths match the glob pattern, e.g. \"**/node_modules\".\n\
+         \n\
+         A pattern starting with \"/\" is matched against absolute paths, \
+         and other patterns are matched against relative paths \
+         with and without the leading \"./\". \
+         Path separators are only matched by \"**\".\n\
+         \n\
+         This option can be specified multiple times. \
+         All patterns are compiled into a single matcher."
+    );
+
+    doc!(
+        help,
+        "exclude-regex",
+        "Remove branches matching a regex pattern.",
+        "Skip the files or do not descend into the directories \
+         of which the paths match the regex pattern, e.g. \"/\\.cache$\".\n\
+         \n\
+         Patterns are matched against relative paths with and without the leading \"./\", \
+         and absolute paths.\n\
+         \n\
+         This option can be specified multiple times. \
+         All patterns are compiled into a single matcher."
+    );
+
     doc!(
         help,
         "unicode",
@@ -294,6 +545,17 @@ fn get_help() -> HashMap<&'static str, Help> {
         "Follow symlinks and traverse the symlinked directories."
     );
 
+    doc!(
+        help,
+        "unique",
+        "Print each file only once.",
+        "Suppress search results of a file that has been found through another path, \
+         e.g. another starting point, a symlink followed by --follow or a hard link. \
+         Files are identified by their device numbers and inode numbers.\n\
+         \n\
+         Which of the paths is printed is undetermined unless --ordered is used."
+    );
+
     doc!(
         help,
         "same-file-system",
@@ -321,6 +583,29 @@ fn get_help() -> HashMap<&'static str, Help> {
          An absolute path may not be the real path due to symlinks."
     );
 
+    doc!(
+        help,
+        "real-path",
+        "Output real paths with symlinks resolved.",
+        "Resolve symlinks in the directories of paths physically, \
+         i.e. resolve symlinks as encountered, like \"pwd -P\". \
+         The last component of a path is kept so that a symlink is still printed as itself.\n\
+         \n\
+         Starting points and paths given by --exclude are resolved in the same way \
+         before searching. Resolved directories are cached."
+    );
+
+    doc!(
+        help,
+        "logical-path",
+        "Output absolute paths with \"..\" resolved.",
+        "Resolve \".\" and \"..\" components lexically before symlinks, \
+         like \"pwd -L\".\n\
+         \n\
+         Starting points and paths given by --exclude are resolved in the same way \
+         before searching."
+    );
+
     doc!(
         help,
         "sort-path",
@@ -333,6 +618,68 @@ fn get_help() -> HashMap<&'static str, Help> {
          This option also forces --exec to use a single thread for processing."
     );
 
+    doc!(
+        help,
+        "sort",
+        "Sort the results by keys: path, size, mtime, name, etc.",
+        concat!(
+            "Sort the search results by these keys before output:\n",
+            "\n",
+            "       path: same as --sort-path\n",
+            "       size: file size\n",
+            "      mtime: last modification time\n",
+            "      atime: last access time\n",
+            "      ctime: last status change time\n",
+            "       name: file name\n",
+            "        ext: file extension\n",
+            "    version: file name with numbers compared by value, e.g. a2 < a10\n",
+            "      depth: search depth\n",
+            "\n",
+            "Multiple keys are specified by separating them with a comma \",\", \
+             e.g. --sort=mtime,name. \
+             Results with equal keys are sorted by pathname. \
+             Metadata of files are collected only when the keys need them.\n",
+            "\n",
+            "This option also forces --exec to use a single thread for processing."
+        )
+    );
+
+    doc!(
+        help,
+        "reverse",
+        "Sort the results in reverse order.",
+        "Reverse the order of sorted search results. \
+         The results are sorted by pathname if no sort key is specified."
+    );
+
+    doc!(
+        help,
+        "ordered",
+        "Search in sorted order and stream the results.",
+        "Walk the directories one by one and read the entries of each directory \
+         in the order of their names, so the search results are sorted like --sort-path \
+         but printed as soon as they are found.\n\
+         \n\
+         Only a single thread is used for searching, which is slower than --sort-path \
+         for a large directory tree. Pruned directories are not descended into. \
+         Results of multiple starting points are printed one starting point after another.\n\
+         \n\
+         This option also forces --exec to use a single thread for processing."
+    );
+
+    doc!(
+        help,
+        "sort-memory",
+        "Set memory budget for sorting. [default: unlimited]",
+        "Limit the memory used for buffering the search results to be sorted, \
+         e.g. 512M. The size is in bytes with an optional unit K, M or G (powers of 1024).\n\
+         \n\
+         Beyond this size, the buffered search results are sorted \
+         and saved to a temporary file, and all temporary files are merged at the end. \
+         The output is the same as sorting in memory. \
+         The budget is only an estimate of the memory used."
+    );
+
     doc!(
         help,
         "dot-files",
@@ -350,7 +697,49 @@ fn get_help() -> HashMap<&'static str, Help> {
         "no-ignore",
         "Do not respect .(git)ignore files.",
         "Show search results from files and directories that would otherwise be ignored by \
-         .(git)ignore files."
+         .(git)ignore files, .ffignore files, the global ignore file of Git \
+         and the ignore files specified with --ignore-file.\n\
+         \n\
+         The ignored predicate of filter chains still respects all of them."
+    );
+
+    doc!(
+        help,
+        "no-ignore-vcs",
+        "Do not respect VCS ignore files.",
+        "Do not respect .gitignore files, .git/info/exclude \
+         and the global ignore file of Git. \
+         The .ignore and .ffignore files are still respected."
+    );
+
+    doc!(
+        help,
+        "no-ignore-parent",
+        "Do not respect ignore files in parent directories.",
+        "Do not respect ignore files in the parent directories of the starting points."
+    );
+
+    doc!(
+        help,
+        "no-global-ignore",
+        "Do not respect the global ignore file.",
+        "Do not respect the global ignore file of Git specified by core.excludesFile, \
+         which defaults to $XDG_CONFIG_HOME/git/ignore."
+    );
+
+    doc!(
+        help,
+        "ignore-file",
+        "Add an ignore file.",
+        "Ignore files and directories matching the patterns in the file, \
+         which has the same syntax as .gitignore files. \
+         The patterns are matched as if the file was in the current working directory. \
+         The file is not respected with --no-ignore.\n\
+         \n\
+         Files and directories can be ignored by the .ffignore files \
+         in the directory trees as well. They have higher precedence than other ignore files.\n\
+         \n\
+         This option can be specified multiple times."
     );
 
     doc!(
@@ -362,6 +751,147 @@ fn get_help() -> HashMap<&'static str, Help> {
          Interactive input is disabled by caching, even if the commands run sequentially."
     );
 
+    doc!(
+        help,
+        "strip-prefix",
+        "Omit the leading \"./\" of printed paths.",
+        "Print relative paths without the leading \"./\" component.\n\
+         \n\
+         This option does not affect the matching of paths nor --exec."
+    );
+
+    doc!(
+        help,
+        "relative-to",
+        "Print paths relative to a directory.",
+        "Print paths relative to the given directory, using \"..\" components as needed.\n\
+         \n\
+         Paths are compared after being transformed into absolute paths, \
+         without resolving symlinks. \
+         This option does not affect the matching of paths nor --exec."
+    );
+
+    doc!(
+        help,
+        "base-name",
+        "Print only the file names.",
+        "Print only the last component of paths.\n\
+         \n\
+         This option does not affect the matching of paths nor --exec."
+    );
+
+    doc!(
+        help,
+        "output-format",
+        "Set output format: plain, json, csv, tsv, long [default: plain]",
+        concat!(
+            "Declare the format of printed search results:\n",
+            "\n",
+            "    plain: one path per line [default]\n",
+            "     json: same as --json\n",
+            "      csv: comma-separated values with a header row (RFC 4180)\n",
+            "      tsv: tab-separated values with a header row\n",
+            "     long: same as --long\n",
+            "\n",
+            "Columns of csv and tsv are selected by --columns. \
+             Fields of csv are quoted when necessary. \
+             Backslashes, tabs and line breaks in fields of tsv are escaped \
+             as \\\\, \\t, \\n and \\r respectively."
+        )
+    );
+
+    doc!(
+        help,
+        "json",
+        "Print each search result as a JSON object.",
+        "Print each search result as a JSON object on a single line (NDJSON).\n\
+         \n\
+         The object contains these fields: \
+         path, type, size, mode, uid, user, gid, group, atime, mtime, ctime, \
+         depth, target (of a symlink) and root (the starting point). \
+         Timestamps are seconds since the Unix epoch. \
+         Unavailable values are null.\n\
+         \n\
+         Strings that are not valid UTF-8 are converted with replacement characters, \
+         and their original bytes are provided by extra fields like path_base64.\n\
+         \n\
+         Objects are terminated with a NUL character instead of a newline by --print0 \
+         or the action print0."
+    );
+
+    doc!(
+        help,
+        "long",
+        "Use a long listing format like \"ls -dils\".",
+        "Print each search result in a long listing format like \"find -ls\":\n\
+         \n\
+         inode, blocks (in 1024-byte units), file mode, number of links, owner, group, \
+         size (in bytes), last modification time, path (followed by \"-> target\" for a symlink).\n\
+         \n\
+         Lines are terminated with a NUL character instead of a newline by --print0 \
+         or the action print0. Lines printed by the action ls always end with a newline."
+    );
+
+    doc!(
+        help,
+        "tree",
+        "Show the results as a tree: unicode, ascii [default: unicode]",
+        concat!(
+            "Print the search results as an indented tree for each starting point, \
+             like the program \"tree\". \
+             Only the directories leading to the search results are shown.\n",
+            "\n",
+            "    unicode: use box-drawing characters [default]\n",
+            "      ascii: use ASCII characters only\n",
+            "\n",
+            "The value must be attached with an equal sign, e.g. --tree=ascii\n",
+            "\n",
+            "All search results are buffered and sorted by pathname before output."
+        )
+    );
+
+    doc!(
+        help,
+        "columns",
+        "Select columns for csv and tsv: path, size, mtime, etc.",
+        concat!(
+            "Select columns for the csv and tsv output formats: [default: path]\n",
+            "\n",
+            "    path, name, type, size, mode, uid, user, gid, group,\n",
+            "    atime, mtime, ctime, depth, target, root\n",
+            "\n",
+            "Multiple columns are specified by separating them with a comma \",\". \
+             The meaning of columns is the same as the fields of --json, \
+             except that mode is in octal \
+             and timestamps are in ISO 8601 format in UTC.\n",
+            "\n",
+            "Metadata of files are collected only when the selected columns need them."
+        )
+    );
+
+    doc!(
+        help,
+        "count",
+        "Print only the number of search results.",
+        "Print the number of search results instead of the search results, \
+         which is safe for file names containing newlines unlike \"ff | wc -l\".\n\
+         \n\
+         Only the search results printed by any action are counted."
+    );
+
+    doc!(
+        help,
+        "stats",
+        "Print the totals of the search to stderr at exit.",
+        "Print the totals of the search to stderr at exit:\n\
+         \n\
+         number of search results, directories visited, \
+         entries skipped by --exclude, ignore files, hidden files or the action prune, \
+         errors, total size of the matched regular files (in bytes) and elapsed time.\n\
+         \n\
+         The entries inside skipped directories are not counted."
+    );
+
     doc!(
         help,
         "file-type",
@@ -390,6 +920,19 @@ fn get_help() -> HashMap<&'static str, Help> {
         "Limit the directory traversal to a given depth."
     );
 
+    doc!(
+        help,
+        "max-results",
+        "Stop searching after a number of results. [default: unlimited]",
+        "Limit the number of search results, and stop searching once they are found.\n\
+         \n\
+         Only the search results printed by any action are counted. \
+         Exactly the first results sorted by pathname are kept \
+         when --sort, --sort-path or --tree is used, at the cost of searching all files."
+    );
+
+    doc!(help, "one", "Same as --max-results=1.");
+
     doc!(
         help,
         "color",
@@ -412,6 +955,14 @@ fn get_help() -> HashMap<&'static str, Help> {
          0 means [default: number of available CPU cores]"
     );
 
+    doc!(
+        help,
+        "sniff-size",
+        "Set number of bytes read for telling text from binary. [default: 8K]",
+        "The number of bytes read from the start of each file by the predicates text and binary, \
+         e.g. 64K. The size is in bytes with an optional unit K, M or G (powers of 1024)."
+    );
+
     doc!(
         help,
         "max-buffer-time",
@@ -519,6 +1070,104 @@ fn get_help() -> HashMap<&'static str, Help> {
             "    * Match specified file types.\n",
             "        type <file type[,file type]...>\n",
             "\n",
+            "    * Match file names with any of the extensions.\n",
+            "        ext <extension[,extension]...>\n",
+            "        iext <extension[,extension]...>\n",
+            "      Extensions can have multiple dots, e.g. ext rs,toml,tar.gz; \
+             the file names must have something before the extensions. \
+             iext is case-insensitive.\n",
+            "\n",
+            "    * Match directories on their paths, the same as type d and path <glob pattern>.\n",
+            "        dir <glob pattern>\n",
+            "\n",
+            "    * Match files of which the names start with a dot \".\".\n",
+            "        hidden\n",
+            "\n",
+            "    * Match files that would be ignored by .(git)ignore files, .ffignore files, \
+             the global ignore file of Git or the files specified with --ignore-file, \
+             regardless of --no-ignore and the likes.\n",
+            "        ignored\n",
+            "      Files in ignored directories are also ignored. \
+             Use it with --no-ignore, e.g. --no-ignore . ignored and name '*.o'\n",
+            "\n",
+            "    * Match files by their status in the Git repositories containing them, \
+             read from the repositories directly.\n",
+            "        tracked\n",
+            "        untracked\n",
+            "        modified\n",
+            "        staged\n",
+            "      Directories are tracked if any file in them is tracked. \
+             Modified files differ from the index; staged files differ between the index and HEAD. \
+             Unmerged files are both modified and staged. \
+             Files in the .git directories and outside of any repository match none of them.\n",
+            "\n",
+            "    * Match regular files that contain the string.\n",
+            "        contains <string>\n",
+            "      The string can span multiple lines.\n",
+            "\n",
+            "    * Match regular files that contain a line matching the regex pattern.\n",
+            "        grep <regex pattern>\n",
+            "      The line terminator \"\\n\" is not part of the lines. \
+             Files are read from the walker threads; \
+             put these predicates after cheaper ones to skip reading files, \
+             e.g. type f and name '*.rs' and grep 'TODO|FIXME'\n",
+            "\n",
+            "    * Match regular files that look like text or not.\n",
+            "        text\n",
+            "        binary\n",
+            "      Files are text if they start with a byte order mark of UTF-8/16/32, \
+             or contain no NUL byte and few invalid UTF-8 sequences or control characters \
+             in the first bytes read (see --sniff-size).\n",
+            "\n",
+            "    * Match regular files by their MIME types (case-insensitive).\n",
+            "        mime <glob pattern>\n",
+            "      The types are guessed from the magic numbers of common formats, \
+             e.g. mime 'image/*' or mime application/pdf. \
+             Files without known magic numbers are guessed from the extensions, \
+             e.g. audio/mpeg for binary .mp3 files and text/x-rust for text .rs files, \
+             defaulting to application/octet-stream or text/plain. \
+             Documents in ZIP and OLE containers are also told apart by the extensions, \
+             e.g. .docx and .xls files. Empty files are inode/x-empty.\n",
+            "\n",
+            "    * Match scripts by the file names of the interpreters in their \"#!\" lines.\n",
+            "        interpreter <glob pattern>\n",
+            "      \"#!/usr/bin/env python3 -u\" is resolved to python3, \
+             e.g. type x and interpreter 'python*'. \
+             Symlinks to scripts are followed.\n",
+            "\n",
+            "    * Match the contents of symlinks (what readlink prints).\n",
+            "        lname <glob pattern>\n",
+            "      The wildcards also match \"/\", e.g. lname '/opt/*'\n",
+            "\n",
+            "    * Perform a case-insensitive match on the contents of symlinks.\n",
+            "        ilname <glob pattern>\n",
+            "\n",
+            "    * Match symlinks of which the targets exist.\n",
+            "        target-exists\n",
+            "\n",
+            "    * Match symlinks of which the targets are inside the starting points.\n",
+            "        target-inside\n",
+            "      Symlinks are resolved fully; broken symlinks are resolved lexically. \
+             Use type l and not target-inside for symlinks pointing outside.\n",
+            "\n",
+            "    * Match files by the numbers of hard links or the inode numbers.\n",
+            "        links [+-]<number>\n",
+            "        inum [+-]<number>\n",
+            "      +N means more than N, -N means less than N, and N means exactly N, \
+             e.g. type f and links +1. \
+             Put -- before the starting point for -N, e.g. ff -- . links -2\n",
+            "\n",
+            "    * Match hard links to the same file as the path.\n",
+            "        samefile <path>\n",
+            "      The path is resolved once before searching.\n",
+            "\n",
+            "    * Match empty regular files and empty directories.\n",
+            "        empty\n",
+            "      Hidden files are not counted in directories without --all, \
+             and neither are ignored files without --no-ignore. \
+             Use it with prune to skip the directories, \
+             e.g. type d and empty and prune and print\n",
+            "\n",
             "    * Always true.\n",
             "        true\n",
             "\n",
@@ -531,19 +1180,23 @@ fn get_help() -> HashMap<&'static str, Help> {
             "    * Always true; print the result followed by a NUL character.\n",
             "        print0\n",
             "\n",
+            "    * Always true; print the result in long listing format like --long.\n",
+            "        ls\n",
+            "\n",
             "    * Always true; do not descend into a directory.\n",
             "        prune\n",
             "      This does not cancel other applied actions.\n",
             "\n",
             "    * Always true; quit searching after applying other actions.\n",
             "        quit\n",
-            "      More results can be produced while this action is accepted.\n",
+            "      More results can be produced while this action is accepted. \
+             Use the option --max-results for an exact number of results.\n",
             "\n",
             "  The head of a predicate is case-insensitive.\n",
             "\n",
             "These predicates are also \"actions\" due to their side effects:\n",
             "\n",
-            "  print, print0, prune, quit.\n",
+            "  print, print0, ls, prune, quit.\n",
             "\n",
             "If no action is specified in the filter chain, \
              all matched results are printed on the standard output \
@@ -0,0 +1,205 @@
+use std::cell::RefCell;
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+use super::globset::{self, GlobSet, GlobSetBuilder};
+use super::regex::bytes::{RegexSet, RegexSetBuilder};
+
+use super::fshelper::logical_path;
+use super::internal::Error;
+use super::pattern::PatternBuilder;
+
+// Glob patterns and regex patterns of excluded paths, each compiled into a single matcher.
+pub struct ExcludeSet {
+    relative_globs: GlobSet,
+    absolute_globs: GlobSet,
+    regexes: RegexSet,
+    cwd: Option<PathBuf>, // joined with relative paths for the absolute forms
+}
+
+thread_local! {
+    // The buffer of the absolute forms is reused for every path.
+    static ABSOLUTE: RefCell<PathBuf> = RefCell::new(PathBuf::new());
+}
+
+impl ExcludeSet {
+    pub fn is_empty(&self) -> bool {
+        self.relative_globs.is_empty() && self.absolute_globs.is_empty() && self.regexes.len() == 0
+    }
+
+    // Glob patterns starting with "/" are matched against the absolute form of the path, and
+    // others are matched against the relative forms with and without the leading "./".
+    // Regex patterns are matched against all of them. The absolute form is only made when no
+    // relative form is matched.
+    pub fn is_match(&self, path: &Path) -> bool {
+        let stripped = path.strip_prefix(".").unwrap_or(path);
+
+        if self.relative_globs.is_match(path) || self.relative_globs.is_match(stripped) {
+            return true;
+        }
+        if self.regexes.is_match(path.as_os_str().as_bytes())
+            || self.regexes.is_match(stripped.as_os_str().as_bytes())
+        {
+            return true;
+        }
+        if self.absolute_globs.is_empty() && self.regexes.len() == 0 {
+            return false;
+        }
+        if path.is_absolute() {
+            return self.absolute_globs.is_match(path);
+        }
+
+        let cwd = match self.cwd {
+            Some(ref cwd) => cwd,
+            None => return false,
+        };
+
+        ABSOLUTE.with(|absolute| {
+            let mut absolute = absolute.borrow_mut();
+
+            // pushing an absolute path replaces the whole path
+            absolute.push(cwd);
+            absolute.push(stripped);
+
+            self.absolute_globs.is_match(&*absolute)
+                || self.regexes.is_match(absolute.as_os_str().as_bytes())
+        })
+    }
+}
+
+pub struct ExcludeSetBuilder {
+    relative_globs: GlobSetBuilder,
+    absolute_globs: GlobSetBuilder,
+    regexes: Vec<String>,
+    unicode: bool,
+}
+
+impl ExcludeSetBuilder {
+    pub fn new() -> ExcludeSetBuilder {
+        ExcludeSetBuilder {
+            relative_globs: GlobSetBuilder::new(),
+            absolute_globs: GlobSetBuilder::new(),
+            regexes: Vec::new(),
+            unicode: false,
+        }
+    }
+
+    pub fn build(&self) -> Result<ExcludeSet, Error> {
+        let to_error = |message: String| Error::from_str(&message);
+
+        Ok(ExcludeSet {
+            relative_globs: self
+                .relative_globs
+                .build()
+                .map_err(|err| to_error(err.to_string()))?,
+            absolute_globs: self
+                .absolute_globs
+                .build()
+                .map_err(|err| to_error(err.to_string()))?,
+            regexes: RegexSetBuilder::new(&self.regexes)
+                .unicode(self.unicode)
+                .dot_matches_new_line(true)
+                .build()
+                .map_err(|err| to_error(err.to_string()))?,
+            cwd: logical_path(Path::new(".")).ok(),
+        })
+    }
+
+    // Path separators are only matched by "**" in glob patterns.
+    pub fn add_glob(&mut self, pattern: &OsStr) -> Result<&mut ExcludeSetBuilder, Error> {
+        // XXX: strange conformance to UTF-8
+        let source = pattern
+            .to_str()
+            .ok_or(Error::from_str("need a UTF-8 encoded pattern"))?;
+        let glob = globset::GlobBuilder::new(source)
+            .unicode(self.unicode)
+            .backslash_escape(true)
+            .literal_separator(true)
+            .build()
+            .map_err(|err| Error::from_str(&err.to_string()))?;
+
+        if source.starts_with('/') {
+            self.absolute_globs.add(glob);
+        } else {
+            self.relative_globs.add(glob);
+        }
+
+        Ok(self)
+    }
+
+    pub fn add_regex(&mut self, pattern: &OsStr) -> Result<&mut ExcludeSetBuilder, Error> {
+        // XXX: strange conformance to UTF-8
+        let source = if self.unicode {
+            pattern
+                .to_str()
+                .map(str::to_string)
+                .ok_or(Error::from_str("need a UTF-8 encoded pattern"))?
+        } else {
+            PatternBuilder::escape_pattern(pattern)
+                .ok_or(Error::from_str("invalid UTF-8 byte sequences found"))?
+        };
+
+        self.regexes.push(source);
+
+        Ok(self)
+    }
+
+    pub fn unicode(&mut self, on: bool) -> &mut ExcludeSetBuilder {
+        self.unicode = on;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(globs: &[&str], regexes: &[&str]) -> ExcludeSet {
+        let mut builder = ExcludeSetBuilder::new();
+
+        for glob in globs {
+            builder.add_glob(OsStr::new(glob)).unwrap();
+        }
+        for regex in regexes {
+            builder.add_regex(OsStr::new(regex)).unwrap();
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn exclude_globs() {
+        let set = build(&["**/node_modules", "one/*", "/tmp/**"], &[]);
+
+        assert!(set.is_match(Path::new("./a/b/node_modules")));
+        assert!(set.is_match(Path::new("node_modules")));
+        assert!(set.is_match(Path::new("./one/two")));
+        assert!(!set.is_match(Path::new("./one/two/three")));
+        assert!(set.is_match(Path::new("/tmp/a")));
+        assert!(!set.is_match(Path::new("./node_modules_old")));
+    }
+
+    #[test]
+    fn exclude_regexes() {
+        let set = build(&[], &[r"\.bak$", r"^one/"]);
+
+        assert!(set.is_match(Path::new("./a.bak")));
+        assert!(set.is_match(Path::new("./one/two")));
+        assert!(!set.is_match(Path::new("./two/one/b")));
+        assert!(!build(&[], &[]).is_match(Path::new("./a")));
+    }
+
+    #[test]
+    fn exclude_absolute() {
+        let cwd = logical_path(Path::new(".")).unwrap();
+        let cwd = cwd.to_str().unwrap();
+        let set = build(&[&format!("{}/one/*", cwd)], &[&format!("^{}/two$", cwd)]);
+
+        assert!(set.is_match(Path::new("./one/a")));
+        assert!(set.is_match(Path::new("one/b")));
+        assert!(set.is_match(Path::new(&format!("{}/one/c", cwd))));
+        assert!(set.is_match(Path::new("two")));
+        assert!(!set.is_match(Path::new("./one/two/three")));
+        assert!(!set.is_match(Path::new("./three/two")));
+    }
+}
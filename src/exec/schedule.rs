@@ -38,7 +38,7 @@ pub fn schedule(
         };
 
         let path = match lock.recv() {
-            Ok(data) => data.0,
+            Ok(data) => data.path,
             Err(_) => break,
         };
 
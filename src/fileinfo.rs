@@ -0,0 +1,86 @@
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+
+// File type bits of st_mode, see inode(7).
+const S_IFMT: u32 = 0o170000;
+const S_IFSOCK: u32 = 0o140000;
+const S_IFLNK: u32 = 0o120000;
+const S_IFREG: u32 = 0o100000;
+const S_IFBLK: u32 = 0o060000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFCHR: u32 = 0o020000;
+const S_IFIFO: u32 = 0o010000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp {
+    pub secs: i64,
+    pub nsecs: i64,
+}
+
+// A snapshot of the metadata collected once for each search result.
+// Unlike std::fs::Metadata, it can be freely copied, compared and stored.
+#[derive(Clone, Debug)]
+pub struct FileInfo {
+    pub ino: u64,
+    pub mode: u32,
+    pub nlink: u64,
+    pub uid: u32,
+    pub gid: u32,
+    pub size: u64,
+    pub blocks: u64, // in 512-byte units
+    pub atime: Timestamp,
+    pub mtime: Timestamp,
+    pub ctime: Timestamp,
+}
+
+impl FileInfo {
+    pub fn from_metadata(meta: &fs::Metadata) -> FileInfo {
+        FileInfo {
+            ino: meta.ino(),
+            mode: meta.mode(),
+            nlink: meta.nlink(),
+            uid: meta.uid(),
+            gid: meta.gid(),
+            size: meta.size(),
+            blocks: meta.blocks(),
+            atime: Timestamp {
+                secs: meta.atime(),
+                nsecs: meta.atime_nsec(),
+            },
+            mtime: Timestamp {
+                secs: meta.mtime(),
+                nsecs: meta.mtime_nsec(),
+            },
+            ctime: Timestamp {
+                secs: meta.ctime(),
+                nsecs: meta.ctime_nsec(),
+            },
+        }
+    }
+
+    // The permission bits including setuid, setgid and sticky bits.
+    pub fn permissions(&self) -> u32 {
+        self.mode & 0o7777
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.mode & S_IFMT == S_IFREG
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.mode & S_IFMT == S_IFLNK
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self.mode & S_IFMT {
+            S_IFDIR => "directory",
+            S_IFREG => "file",
+            S_IFLNK => "symlink",
+            S_IFBLK => "block_device",
+            S_IFCHR => "char_device",
+            S_IFIFO => "fifo",
+            S_IFSOCK => "socket",
+            _ => "unknown",
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use super::*;
+
+// A numeric argument like find(1): +N for more than N, -N for less than N, N for exactly N.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Less(u64),
+    Equal(u64),
+    More(u64),
+}
+
+impl Comparison {
+    pub fn from_str(text: &OsStr) -> Result<Comparison, Error> {
+        let bytes = text.as_bytes();
+        let (digits, constructor): (&[u8], fn(u64) -> Comparison) = match bytes.first() {
+            Some(b'+') => (&bytes[1..], Comparison::More),
+            Some(b'-') => (&bytes[1..], Comparison::Less),
+            _ => (bytes, Comparison::Equal),
+        };
+
+        std::str::from_utf8(digits)
+            .ok()
+            .filter(|digits| digits.bytes().all(|byte| byte.is_ascii_digit()))
+            .and_then(|digits| digits.parse().ok())
+            .map(constructor)
+            .ok_or_else(|| Error::from_str(&format!("found invalid number {:?}", text)))
+    }
+
+    pub fn test(&self, number: u64) -> bool {
+        match *self {
+            Comparison::Less(n) => number < n,
+            Comparison::Equal(n) => number == n,
+            Comparison::More(n) => number > n,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comparison() {
+        let parse = |text: &str| Comparison::from_str(OsStr::new(text)).ok();
+
+        assert_eq!(parse("1"), Some(Comparison::Equal(1)));
+        assert_eq!(parse("+1"), Some(Comparison::More(1)));
+        assert_eq!(parse("-2"), Some(Comparison::Less(2)));
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("+"), None);
+        assert_eq!(parse("+-1"), None);
+        assert_eq!(parse("1k"), None);
+        assert!(Comparison::More(1).test(2));
+        assert!(!Comparison::More(1).test(1));
+        assert!(Comparison::Less(2).test(1));
+        assert!(!Comparison::Equal(2).test(1));
+    }
+}
@@ -0,0 +1,279 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use std::str;
+
+use super::super::memchr::memchr;
+use super::super::regex::bytes::Regex;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+// Lines longer than this are searched in windows overlapping by CHUNK_SIZE bytes.
+const MAX_LINE: usize = 1024 * 1024;
+
+// A search of file contents. Files are streamed instead of loaded into memory.
+pub enum ContentMatcher {
+    Literal(Vec<u8>), // matched anywhere, even across lines
+    Regex(Regex),     // matched line by line like grep(1)
+}
+
+impl std::fmt::Debug for ContentMatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            ContentMatcher::Literal(literal) => {
+                write!(f, "Literal({:?})", String::from_utf8_lossy(literal))
+            }
+            ContentMatcher::Regex(regex) => write!(f, "Regex({:?})", regex.as_str()),
+        }
+    }
+}
+
+impl ContentMatcher {
+    pub fn is_match(&self, path: &Path) -> io::Result<bool> {
+        let file = File::open(path)?;
+
+        match self {
+            ContentMatcher::Literal(literal) => find_literal(file, literal, CHUNK_SIZE),
+            ContentMatcher::Regex(regex) => find_regex(file, regex, MAX_LINE, CHUNK_SIZE),
+        }
+    }
+}
+
+fn find_in(haystack: &[u8], needle: &[u8]) -> bool {
+    let mut start = 0;
+
+    while let Some(offset) = memchr(needle[0], &haystack[start..]) {
+        let position = start + offset;
+
+        if haystack[position..].starts_with(needle) {
+            return true;
+        }
+        start = position + 1;
+    }
+
+    false
+}
+
+fn find_literal<R: Read>(mut reader: R, literal: &[u8], chunk_size: usize) -> io::Result<bool> {
+    if literal.is_empty() {
+        return Ok(true);
+    }
+
+    // keep the tail of the previous chunk for matches across chunks
+    let overlap = literal.len() - 1;
+    let mut buffer = vec![0; overlap + chunk_size.max(literal.len())];
+    let mut kept = 0;
+
+    loop {
+        let count = match reader.read(&mut buffer[kept..]) {
+            Ok(0) => return Ok(false),
+            Ok(count) => count,
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        let filled = kept + count;
+
+        if find_in(&buffer[..filled], literal) {
+            return Ok(true);
+        }
+
+        kept = overlap.min(filled);
+        buffer.copy_within(filled - kept..filled, 0);
+    }
+}
+
+// Very long lines are cut into windows to keep memory use bounded, so matches longer than the
+// overlap or close to the ends of the windows in such lines may be missed.
+fn find_regex<R: Read>(
+    reader: R,
+    regex: &Regex,
+    max_line: usize,
+    overlap: usize,
+) -> io::Result<bool> {
+    let mut reader = BufReader::with_capacity(CHUNK_SIZE, reader);
+    let mut line = Vec::new();
+    let mut context = 0; // the byte before the window, kept for anchors like ^ and \b
+    let mut pending = false; // whether there is a line to search
+
+    loop {
+        let (complete, used) = match reader.fill_buf() {
+            Ok(data) => match memchr(b'\n', data) {
+                Some(end) => {
+                    line.extend_from_slice(&data[..end]);
+                    (true, end + 1)
+                }
+                None => {
+                    line.extend_from_slice(data);
+                    (data.is_empty(), data.len())
+                }
+            },
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+
+        reader.consume(used);
+        pending |= used > 0;
+
+        if complete {
+            // end of file
+            if !pending {
+                return Ok(false);
+            }
+            if regex.is_match_at(&line, context) {
+                return Ok(true);
+            }
+            if used == 0 {
+                return Ok(false);
+            }
+            line.clear();
+            context = 0;
+            pending = false;
+        } else if line.len() >= max_line {
+            // a match at the end of the window may be a false one of $ or continue in the
+            // next window
+            if regex
+                .find_at(&line, context)
+                .map_or(false, |found| found.end() < line.len())
+            {
+                return Ok(true);
+            }
+
+            let keep = line.len().min(overlap + 1);
+            let start = line.len() - keep;
+
+            // the rest is searched again at the end of the line
+            line.drain(..start);
+            context = 1;
+        }
+    }
+}
+
+// The byte order marks of UTF-32 must be checked before those of UTF-16.
+const BOMS: [&[u8]; 5] = [
+    b"\xFF\xFE\x00\x00", // UTF-32LE
+    b"\x00\x00\xFE\xFF", // UTF-32BE
+    b"\xEF\xBB\xBF",     // UTF-8
+    b"\xFF\xFE",         // UTF-16LE
+    b"\xFE\xFF",         // UTF-16BE
+];
+
+// Read at most the first bytes of a file.
+pub fn read_head(path: &Path, size: usize) -> io::Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(size.min(CHUNK_SIZE));
+
+    File::open(path)?.take(size as u64).read_to_end(&mut data)?;
+
+    Ok(data)
+}
+
+// Whether the file looks like a binary file by the first bytes of it.
+pub fn is_binary(path: &Path, sniff_size: usize) -> io::Result<bool> {
+    let data = read_head(path, sniff_size)?;
+
+    Ok(looks_binary(&data, data.len() < sniff_size))
+}
+
+// Text files start with a byte order mark, or contain no NUL byte and
+// few invalid UTF-8 sequences or control characters (e.g. Latin-1 text passes).
+pub fn looks_binary(data: &[u8], complete: bool) -> bool {
+    if BOMS.iter().any(|bom| data.starts_with(bom)) {
+        return false;
+    }
+    if memchr(0, data).is_some() {
+        return true;
+    }
+
+    let mut suspicious = data
+        .iter()
+        .filter(|&&byte| {
+            (byte < 0x20 && !b"\t\n\r\x0B\x0C\x1B\x08".contains(&byte)) || byte == 0x7F
+        })
+        .count();
+    let mut rest = data;
+
+    while let Err(err) = str::from_utf8(rest) {
+        let invalid = &rest[err.valid_up_to()..];
+
+        match err.error_len() {
+            Some(len) => {
+                suspicious += len;
+                rest = &invalid[len..];
+            }
+            None => {
+                // the last sequence may be cut off by the sniff size
+                if complete {
+                    suspicious += invalid.len();
+                }
+                break;
+            }
+        }
+    }
+
+    suspicious * 10 > data.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_search() {
+        let text = b"one two\nthree four\nfive";
+
+        for &chunk_size in &[1, 2, 3, 5, 64] {
+            let find = |literal: &[u8]| find_literal(&text[..], literal, chunk_size).unwrap();
+
+            assert!(find(b""));
+            assert!(find(b"one"));
+            assert!(find(b"two\nthree"));
+            assert!(find(b"five"));
+            assert!(!find(b"fives"));
+            assert!(!find(b"four five"));
+        }
+
+        for &(max_line, overlap) in &[(MAX_LINE, CHUNK_SIZE), (4, 2), (2, 1)] {
+            let find = |regex: &str| {
+                find_regex(&text[..], &Regex::new(regex).unwrap(), max_line, overlap).unwrap()
+            };
+
+            assert!(find(r"^f"));
+            assert!(find(r"e$"));
+            assert!(!find(r"two\sthree"));
+            assert!(!find(r"^two"));
+            assert!(!find(r"^o$"));
+        }
+
+        let text = b"\n\nlong line with zzz in it\nend\n";
+
+        for &(max_line, overlap) in &[(MAX_LINE, CHUNK_SIZE), (8, 4)] {
+            let find = |regex: &str| {
+                find_regex(&text[..], &Regex::new(regex).unwrap(), max_line, overlap).unwrap()
+            };
+
+            assert!(find(r"^$"));
+            assert!(find(r"zzz"));
+            assert!(find(r"h z"));
+            assert!(find(r"it$"));
+            assert!(find(r"^end$"));
+            assert!(!find(r"^line"));
+            assert!(!find(r"long$"));
+            assert!(!find(r"yyy"));
+        }
+    }
+
+    #[test]
+    fn content_sniff() {
+        assert!(!looks_binary(b"", true));
+        assert!(!looks_binary(b"plain text\r\n\ttabbed\x1B[0m\n", true));
+        assert!(!looks_binary("utf-8 文字\n".as_bytes(), true));
+        assert!(!looks_binary(b"latin-1 caf\xE9 na\xEFve text\n", true));
+        assert!(!looks_binary(b"\xFF\xFEu\x00t\x00f\x00", true));
+        assert!(!looks_binary(b"\xEF\xBB\xBFbom", true));
+        assert!(looks_binary(b"text with a \x00 byte", true));
+        assert!(looks_binary(b"\x7FELF\x02\x01\x01\xFF\xFE", true));
+        assert!(looks_binary(b"\xFF\xD8\xFF\xE0", true));
+
+        // cut off in the middle of a character
+        assert!(!looks_binary(&"文".as_bytes()[..2], false));
+        assert!(looks_binary(&"文".as_bytes()[..2], true));
+    }
+}
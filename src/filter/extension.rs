@@ -0,0 +1,98 @@
+use std::ffi::OsStr;
+
+use super::super::globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use super::super::internal::Error;
+
+// File name extensions compiled into a single matcher. Extensions can have multiple dots,
+// e.g. "tar.gz", and the names must not be the extensions only, e.g. ".rs" is not an .rs file.
+pub struct ExtensionSet {
+    extensions: Vec<String>,
+    set: GlobSet,
+}
+
+impl std::fmt::Debug for ExtensionSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self.extensions)
+    }
+}
+
+impl ExtensionSet {
+    pub fn new(extensions: &[&OsStr], case_insensitive: bool) -> Result<ExtensionSet, Error> {
+        let mut builder = GlobSetBuilder::new();
+        let mut names = Vec::with_capacity(extensions.len());
+
+        for ext in extensions {
+            let ext = ext.to_str().ok_or_else(|| {
+                Error::from_str(&format!("need a UTF-8 encoded extension {:?}", ext))
+            })?;
+            // the leading dot is optional
+            let ext = ext.trim_start_matches('.');
+
+            if ext.is_empty() {
+                return Err(Error::from_str("found empty extension"));
+            }
+
+            let glob = GlobBuilder::new(&format!("?*.{}", escape(ext)))
+                .backslash_escape(true)
+                .case_insensitive(case_insensitive)
+                .literal_separator(true)
+                .build()
+                .map_err(|err| Error::from_str(&err.to_string()))?;
+
+            builder.add(glob);
+            names.push(ext.to_string());
+        }
+
+        Ok(ExtensionSet {
+            extensions: names,
+            set: builder
+                .build()
+                .map_err(|err| Error::from_str(&err.to_string()))?,
+        })
+    }
+
+    pub fn is_match(&self, name: &OsStr) -> bool {
+        self.set.is_match(name)
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if "\\*?[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extension_set() {
+        let exts = ["rs", ".tar.gz", "[x]"]
+            .iter()
+            .map(OsStr::new)
+            .collect::<Vec<_>>();
+        let set = ExtensionSet::new(&exts, false).unwrap();
+
+        assert!(set.is_match(OsStr::new("main.rs")));
+        assert!(set.is_match(OsStr::new("a.b.rs")));
+        assert!(set.is_match(OsStr::new("backup.tar.gz")));
+        assert!(set.is_match(OsStr::new("weird.[x]")));
+        assert!(!set.is_match(OsStr::new(".rs")));
+        assert!(!set.is_match(OsStr::new("main.RS")));
+        assert!(!set.is_match(OsStr::new("backup.gz")));
+        assert!(!set.is_match(OsStr::new("weird.x")));
+
+        let set = ExtensionSet::new(&exts[..1], true).unwrap();
+
+        assert!(set.is_match(OsStr::new("main.RS")));
+        assert!(ExtensionSet::new(&[OsStr::new(".")], false).is_err());
+    }
+}
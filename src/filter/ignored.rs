@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::super::ignore::gitignore::{Gitignore, GitignoreBuilder};
+use super::super::ignore::{Error as IgnoreError, Match};
+
+use super::super::fshelper::logical_path;
+use super::super::internal::Error;
+
+// The most directories cached for the ignore files in them and whether they are ignored.
+const DIRS_LIMIT: usize = 4096;
+
+// The ignore files in a directory.
+struct DirIgnores {
+    has_git: bool,
+    custom: Option<Gitignore>,      // .ffignore
+    ignore: Option<Gitignore>,      // .ignore
+    git_ignore: Option<Gitignore>,  // .gitignore
+    git_exclude: Option<Gitignore>, // .git/info/exclude
+}
+
+// Get one kind of the ignore files in a directory.
+type IgnoreKind = fn(&DirIgnores) -> Option<&Gitignore>;
+
+impl DirIgnores {
+    fn load(dir: &Path) -> DirIgnores {
+        let read = |path: PathBuf| {
+            if path.is_file() {
+                Some(Gitignore::new(path).0)
+            } else {
+                None
+            }
+        };
+        let has_git = dir.join(".git").exists();
+
+        DirIgnores {
+            has_git,
+            custom: read(dir.join(".ffignore")),
+            ignore: read(dir.join(".ignore")),
+            git_ignore: read(dir.join(".gitignore")),
+            git_exclude: if has_git {
+                read(dir.join(".git/info/exclude"))
+            } else {
+                None
+            },
+        }
+    }
+}
+
+// Decide whether a path would be ignored by the walker if all ignore files were respected,
+// with the same precedence as the walker: .ffignore, .ignore, .gitignore, .git/info/exclude,
+// the global ignore file of Git, and then the ignore files specified with --ignore-file.
+// A path is also ignored if any of its parent directories is ignored.
+//
+// The global ignore file and the ignore files specified with --ignore-file are rooted at the
+// current working directory like the walker does for relative starting points.
+pub struct IgnoreMatcher {
+    global: Gitignore,
+    explicit: Vec<Gitignore>,
+    dirs: Mutex<HashMap<PathBuf, Arc<DirIgnores>>>,
+    ignored_dirs: Mutex<HashMap<PathBuf, bool>>,
+}
+
+impl std::fmt::Debug for IgnoreMatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "IgnoreMatcher({} explicit)", self.explicit.len())
+    }
+}
+
+impl IgnoreMatcher {
+    pub fn new(ignore_files: &[PathBuf]) -> Result<IgnoreMatcher, Error> {
+        let cwd = logical_path(Path::new(".")).map_err(|err| Error::from_str(&err.to_string()))?;
+        let mut explicit = Vec::new();
+
+        for path in ignore_files {
+            let to_error = |err: IgnoreError| {
+                Error::from_str(&format!("failed to read ignore file {:?}: {}", path, err))
+            };
+            let mut builder = GitignoreBuilder::new(&cwd);
+
+            if let Some(err) = builder.add(path) {
+                return Err(to_error(err));
+            }
+            explicit.push(builder.build().map_err(to_error)?);
+        }
+
+        Ok(IgnoreMatcher {
+            // errors are not reported by the walker either
+            global: GitignoreBuilder::new(&cwd).build_global().0,
+            explicit,
+            dirs: Mutex::new(HashMap::new()),
+            ignored_dirs: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        // the lexical form keeps the parent directories matched by their own ignore files
+        let path = match logical_path(path) {
+            Ok(path) => path,
+            Err(_) => return false,
+        };
+
+        match path.parent() {
+            Some(parent) if self.is_ignored_dir(parent) => true,
+            _ => self.matched(&path, is_dir),
+        }
+    }
+
+    fn is_ignored_dir(&self, dir: &Path) -> bool {
+        if let Some(&ignored) = self.ignored_dirs.lock().unwrap().get(dir) {
+            return ignored;
+        }
+
+        let ignored = match dir.parent() {
+            Some(parent) if self.is_ignored_dir(parent) => true,
+            _ => self.matched(dir, true),
+        };
+
+        insert_limited(&self.ignored_dirs, dir, ignored);
+
+        ignored
+    }
+
+    fn dir_ignores(&self, dir: &Path) -> Arc<DirIgnores> {
+        if let Some(ignores) = self.dirs.lock().unwrap().get(dir) {
+            return Arc::clone(ignores);
+        }
+
+        let ignores = Arc::new(DirIgnores::load(dir));
+
+        insert_limited(&self.dirs, dir, Arc::clone(&ignores));
+
+        ignores
+    }
+
+    // Match the path itself against the ignore files in its parent directories.
+    fn matched(&self, path: &Path, is_dir: bool) -> bool {
+        let dirs: Vec<Arc<DirIgnores>> = path
+            .ancestors()
+            .skip(1)
+            .map(|dir| self.dir_ignores(dir))
+            .collect();
+        // the ignore files of Git are only respected inside a Git repository
+        let git_dirs = match dirs.iter().position(|ignores| ignores.has_git) {
+            Some(i) => i + 1,
+            None => 0,
+        };
+        let kinds: [IgnoreKind; 4] = [
+            |ignores| ignores.custom.as_ref(),
+            |ignores| ignores.ignore.as_ref(),
+            |ignores| ignores.git_ignore.as_ref(),
+            |ignores| ignores.git_exclude.as_ref(),
+        ];
+
+        for (i, kind) in kinds.iter().enumerate() {
+            let count = if i < 2 { dirs.len() } else { git_dirs };
+
+            for ignores in &dirs[..count] {
+                if let Some(gitignore) = kind(ignores) {
+                    match gitignore.matched(path, is_dir) {
+                        Match::Ignore(_) => return true,
+                        Match::Whitelist(_) => return false,
+                        Match::None => (),
+                    }
+                }
+            }
+        }
+
+        let global = if git_dirs > 0 {
+            self.global.matched(path, is_dir)
+        } else {
+            Match::None
+        };
+
+        match global {
+            Match::Ignore(_) => true,
+            Match::Whitelist(_) => false,
+            Match::None => self
+                .explicit
+                .iter()
+                .rev()
+                .map(|gitignore| gitignore.matched(path, is_dir))
+                .find(|matched| !matched.is_none())
+                .map_or(false, |matched| matched.is_ignore()),
+        }
+    }
+}
+
+// Directories are mostly visited one by one, so the cache is simply cleared when it is full.
+fn insert_limited<T>(cache: &Mutex<HashMap<PathBuf, T>>, dir: &Path, value: T) {
+    let mut cache = cache.lock().unwrap();
+
+    if cache.len() >= DIRS_LIMIT {
+        cache.clear();
+    }
+    cache.insert(dir.to_path_buf(), value);
+}
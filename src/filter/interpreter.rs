@@ -0,0 +1,66 @@
+use std::ffi::OsStr;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+use super::content::read_head;
+
+// The maximum length of the "#!" line read by execve(2) on Linux.
+const MAX_SHEBANG: usize = 256;
+
+// The interpreter of a script in the "#!" line, e.g. "python3" for "#!/usr/bin/python3 -u"
+// or "#!/usr/bin/env python3". Only the file name of the interpreter is returned.
+pub fn interpreter(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    read_head(path, MAX_SHEBANG).map(|data| parse_shebang(&data).map(|name| name.to_vec()))
+}
+
+fn parse_shebang(data: &[u8]) -> Option<&[u8]> {
+    if !data.starts_with(b"#!") {
+        return None;
+    }
+
+    let line = data[2..].split(|&byte| byte == b'\n').next().unwrap();
+    let mut words = line
+        .split(|&byte| byte == b' ' || byte == b'\t' || byte == b'\r')
+        .filter(|word| !word.is_empty());
+    let mut name = file_name(words.next()?);
+
+    if name == b"env" {
+        // skip options like -S and variable assignments like A=1
+        name = file_name(words.find(|word| !word.starts_with(b"-") && !word.contains(&b'='))?);
+    }
+
+    Some(name)
+}
+
+fn file_name(path: &[u8]) -> &[u8] {
+    Path::new(OsStr::from_bytes(path))
+        .file_name()
+        .map_or(path, |name| name.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shebang_interpreter() {
+        let parse = |data: &[u8]| parse_shebang(data).map(|name| name.to_vec());
+
+        assert_eq!(parse(b"#!/bin/sh\necho"), Some(b"sh".to_vec()));
+        assert_eq!(
+            parse(b"#! /usr/bin/python3 -u\r\n"),
+            Some(b"python3".to_vec())
+        );
+        assert_eq!(parse(b"#!/usr/bin/env node\n"), Some(b"node".to_vec()));
+        assert_eq!(
+            parse(b"#!/usr/bin/env -S A=1 perl -w\n"),
+            Some(b"perl".to_vec())
+        );
+        assert_eq!(parse(b"#!bash"), Some(b"bash".to_vec()));
+        assert_eq!(parse(b"#!/usr/bin/env\n"), None);
+        assert_eq!(parse(b"#!\n/bin/sh"), None);
+        assert_eq!(parse(b"\xEF\xBB\xBF#!/bin/sh\n"), None);
+        assert_eq!(parse(b""), None);
+    }
+}
@@ -0,0 +1,285 @@
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+use super::content::{looks_binary, read_head};
+
+// Every part of a signature is a pair of (offset, bytes).
+type Signature = &'static [(usize, &'static [u8])];
+
+// The signatures are checked in order, so more specific ones go first.
+const MAGIC: &[(Signature, &str)] = &[
+    // images
+    (&[(0, b"\x89PNG\r\n\x1A\n")], "image/png"),
+    (&[(0, b"\xFF\xD8\xFF")], "image/jpeg"),
+    (&[(0, b"GIF87a")], "image/gif"),
+    (&[(0, b"GIF89a")], "image/gif"),
+    (&[(0, b"RIFF"), (8, b"WEBP")], "image/webp"),
+    (&[(0, b"II*\x00")], "image/tiff"),
+    (&[(0, b"MM\x00*")], "image/tiff"),
+    (&[(4, b"ftypavif")], "image/avif"),
+    (&[(4, b"ftypheic")], "image/heic"),
+    // audio and video
+    (&[(0, b"RIFF"), (8, b"WAVE")], "audio/wav"),
+    (&[(0, b"RIFF"), (8, b"AVI ")], "video/x-msvideo"),
+    (&[(0, b"fLaC")], "audio/flac"),
+    (&[(0, b"OggS")], "audio/ogg"),
+    (&[(4, b"ftypM4A")], "audio/mp4"),
+    (&[(4, b"ftypqt")], "video/quicktime"),
+    (&[(4, b"ftyp")], "video/mp4"),
+    (&[(0, b"\x1A\x45\xDF\xA3")], "video/x-matroska"),
+    // documents
+    (&[(0, b"%PDF-")], "application/pdf"),
+    (
+        &[(0, b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1")],
+        "application/x-ole-storage",
+    ),
+    (&[(0, b"SQLite format 3\x00")], "application/vnd.sqlite3"),
+    // fonts
+    (&[(0, b"wOFF")], "font/woff"),
+    (&[(0, b"wOF2")], "font/woff2"),
+    (&[(0, b"OTTO")], "font/otf"),
+    // archives
+    (&[(0, b"PK\x03\x04")], "application/zip"),
+    (&[(0, b"PK\x05\x06")], "application/zip"),
+    (&[(0, b"\x1F\x8B")], "application/gzip"),
+    (&[(0, b"\xFD7zXZ\x00")], "application/x-xz"),
+    (&[(0, b"\x28\xB5\x2F\xFD")], "application/zstd"),
+    (&[(0, b"7z\xBC\xAF\x27\x1C")], "application/x-7z-compressed"),
+    (&[(0, b"Rar!\x1A\x07")], "application/vnd.rar"),
+    (&[(257, b"ustar")], "application/x-tar"),
+    // executables
+    (&[(0, b"\x7FELF")], "application/x-executable"),
+    (&[(0, b"\xCF\xFA\xED\xFE")], "application/x-mach-binary"),
+    (&[(0, b"\xCE\xFA\xED\xFE")], "application/x-mach-binary"),
+    (&[(0, b"\xCA\xFE\xBA\xBE")], "application/java-vm"),
+    (&[(0, b"\x00asm")], "application/wasm"),
+];
+
+// Short signatures which plain text can start with by chance, so they are only checked for
+// binary data.
+const WEAK_MAGIC: &[(Signature, &str)] = &[
+    (&[(0, b"\x00\x00\x01\x00")], "image/vnd.microsoft.icon"),
+    (&[(0, b"BM"), (6, b"\x00\x00\x00\x00")], "image/bmp"),
+    (&[(0, b"ID3")], "audio/mpeg"),
+    (&[(0, b"\x00\x01\x00\x00\x00")], "font/ttf"),
+    (&[(0, b"BZh"), (4, b"1AY&SY")], "application/x-bzip2"),
+    (
+        &[(0, b"MZ")],
+        "application/vnd.microsoft.portable-executable",
+    ),
+];
+
+// enough for the signatures above
+const MAGIC_SIZE: usize = 512;
+
+// Text formats have no magic numbers, so their types come from the extensions.
+const TEXT_EXTENSIONS: &[(&str, &str)] = &[
+    ("c", "text/x-c"),
+    ("cc", "text/x-c++"),
+    ("cpp", "text/x-c++"),
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("go", "text/x-go"),
+    ("h", "text/x-c"),
+    ("hpp", "text/x-c++"),
+    ("htm", "text/html"),
+    ("html", "text/html"),
+    ("java", "text/x-java"),
+    ("js", "text/javascript"),
+    ("json", "application/json"),
+    ("md", "text/markdown"),
+    ("mjs", "text/javascript"),
+    ("py", "text/x-python"),
+    ("rb", "text/x-ruby"),
+    ("rs", "text/x-rust"),
+    ("rtf", "text/rtf"),
+    ("sh", "application/x-sh"),
+    ("svg", "image/svg+xml"),
+    ("toml", "application/toml"),
+    ("ts", "text/typescript"),
+    ("tsv", "text/tab-separated-values"),
+    ("txt", "text/plain"),
+    ("xml", "application/xml"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+];
+
+// Binary formats without reliable magic numbers, and formats stored in generic containers
+// like ZIP and OLE, which refine the types guessed from the magic numbers of the containers.
+const BINARY_EXTENSIONS: &[(&str, &str, Option<&str>)] = &[
+    ("aac", "audio/aac", None),
+    ("apk", "application/vnd.android.package-archive", Some(ZIP)),
+    ("bmp", "image/bmp", None),
+    ("doc", "application/msword", Some(OLE)),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        Some(ZIP),
+    ),
+    ("eot", "application/vnd.ms-fontobject", None),
+    ("epub", "application/epub+zip", Some(ZIP)),
+    ("ico", "image/vnd.microsoft.icon", None),
+    ("iso", "application/x-iso9660-image", None),
+    ("jar", "application/java-archive", Some(ZIP)),
+    ("mp3", "audio/mpeg", None),
+    ("msi", "application/x-msi", Some(OLE)),
+    (
+        "odp",
+        "application/vnd.oasis.opendocument.presentation",
+        Some(ZIP),
+    ),
+    (
+        "ods",
+        "application/vnd.oasis.opendocument.spreadsheet",
+        Some(ZIP),
+    ),
+    ("odt", "application/vnd.oasis.opendocument.text", Some(ZIP)),
+    ("ppt", "application/vnd.ms-powerpoint", Some(OLE)),
+    (
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        Some(ZIP),
+    ),
+    ("pyc", "application/x-python-code", None),
+    ("ttf", "font/ttf", None),
+    ("xls", "application/vnd.ms-excel", Some(OLE)),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        Some(ZIP),
+    ),
+];
+
+const ZIP: &str = "application/zip";
+const OLE: &str = "application/x-ole-storage";
+
+// Guess the MIME type of a regular file by its magic number, or by its extension if there is
+// no magic number matched.
+pub fn mime_type(path: &Path, sniff_size: usize) -> io::Result<&'static str> {
+    let limit = sniff_size.max(MAGIC_SIZE);
+    let data = read_head(path, limit)?;
+    let sniffed = &data[..data.len().min(sniff_size)];
+    let complete = data.len() < limit && sniffed.len() == data.len();
+
+    Ok(guess(
+        &data,
+        looks_binary(sniffed, complete),
+        path.extension().map(|ext| ext.as_bytes()),
+    ))
+}
+
+fn guess(data: &[u8], binary: bool, extension: Option<&[u8]>) -> &'static str {
+    if data.is_empty() {
+        return "inode/x-empty";
+    }
+
+    let extension = extension.map(|ext| ext.to_ascii_lowercase());
+    let is_extension = |name: &str| {
+        extension
+            .as_ref()
+            .map_or(false, |ext| name.as_bytes() == &ext[..])
+    };
+    let weak = if binary { WEAK_MAGIC } else { &[] };
+
+    for &(signature, mime) in MAGIC.iter().chain(weak) {
+        let matched = signature
+            .iter()
+            .all(|&(offset, bytes)| data.get(offset..offset + bytes.len()) == Some(bytes));
+
+        if matched {
+            return BINARY_EXTENSIONS
+                .iter()
+                .find(|&&(name, _, container)| container == Some(mime) && is_extension(name))
+                .map_or(mime, |&(_, mime, _)| mime);
+        }
+    }
+
+    if binary {
+        return BINARY_EXTENSIONS
+            .iter()
+            .find(|&&(name, _, container)| container.is_none() && is_extension(name))
+            .map_or("application/octet-stream", |&(_, mime, _)| mime);
+    }
+
+    TEXT_EXTENSIONS
+        .iter()
+        .find(|&&(name, _)| is_extension(name))
+        .map_or("text/plain", |&(_, mime)| mime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mime_guess() {
+        let mut tar = vec![0; 512];
+
+        tar[257..262].copy_from_slice(b"ustar");
+
+        assert_eq!(guess(b"", false, None), "inode/x-empty");
+        assert_eq!(
+            guess(b"\x89PNG\r\n\x1A\n\x00", true, Some(b"jpg")),
+            "image/png"
+        );
+        assert_eq!(
+            guess(b"RIFF\x00\x00\x00\x00WEBPVP8 ", true, None),
+            "image/webp"
+        );
+        assert_eq!(
+            guess(b"RIFF\x00\x00\x00\x00WAVEfmt ", true, None),
+            "audio/wav"
+        );
+        assert_eq!(guess(&tar, true, Some(b"txt")), "application/x-tar");
+        assert_eq!(guess(b"fn main() {}\n", false, Some(b"RS")), "text/x-rust");
+        assert_eq!(guess(b"fn main() {}\n", false, Some(b"png")), "text/plain");
+        assert_eq!(
+            guess(b"\x00\x01\x02", true, Some(b"rs")),
+            "application/octet-stream"
+        );
+
+        // short signatures are ignored for text
+        assert_eq!(
+            guess(b"MZ notes about things\n", false, Some(b"txt")),
+            "text/plain"
+        );
+        assert_eq!(guess(b"BZh is a prefix\n", false, None), "text/plain");
+        assert_eq!(
+            guess(b"MZ\x90\x00\x03\x00\x00\x00", true, None),
+            "application/vnd.microsoft.portable-executable"
+        );
+        assert_eq!(guess(b"BZh91AY&SY\x00", true, None), "application/x-bzip2");
+        assert_eq!(guess(b"BZh\x00", true, None), "application/octet-stream");
+
+        // binary formats without magic numbers matched
+        assert_eq!(guess(b"\xFF\xFB\x90\x00", true, Some(b"MP3")), "audio/mpeg");
+        assert_eq!(
+            guess(b"\xFF\xFB\x90\x00", true, Some(b"mp4")),
+            "application/octet-stream"
+        );
+        assert_eq!(guess(b"\x00\x01\x02", true, Some(b"ttf")), "font/ttf");
+        assert_eq!(
+            guess(b"\xFF\xFB\x90\x00", false, Some(b"mp3")),
+            "text/plain"
+        );
+
+        // formats in generic containers
+        assert_eq!(
+            guess(b"PK\x03\x04\x14\x00", true, Some(b"docx")),
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+        );
+        assert_eq!(
+            guess(b"PK\x03\x04\x14\x00", true, Some(b"xls")),
+            "application/zip"
+        );
+        assert_eq!(
+            guess(b"PK\x03\x04\x14\x00", true, Some(b"mp3")),
+            "application/zip"
+        );
+        assert_eq!(
+            guess(b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1\x00", true, Some(b"xls")),
+            "application/vnd.ms-excel"
+        );
+    }
+}
@@ -1,14 +1,33 @@
+mod comparison;
+mod content;
+mod extension;
 mod filetype;
+mod ignored;
+mod interpreter;
+mod mime;
 mod parser;
 mod reduction; // FIXME: experimental!
+mod symlink;
+
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
 
 use super::foss::*;
 use super::fshelper::{is_executable, to_absolute_path};
+use super::git::{self, GitStatus};
 use super::internal::{die, warn, AppOptions, Error};
 use super::pattern::{Pattern, PatternBuilder};
 use super::walk::DirEntry;
 
+pub use self::comparison::Comparison;
+pub use self::content::{is_binary, ContentMatcher};
+pub use self::extension::ExtensionSet;
 pub use self::filetype::*;
+pub use self::ignored::IgnoreMatcher;
+pub use self::interpreter::interpreter;
+pub use self::mime::mime_type;
+pub use self::symlink::is_target_inside;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Action {
@@ -17,6 +36,7 @@ pub enum Action {
     // bash$ ff ... pred1 fprint0 >(xargs -0 ...) or pred2 fprint0 >(xargs -0 ...)
     Print,
     Print0,
+    Ls,
     Prune,
     Quit,
 }
@@ -27,6 +47,23 @@ pub enum Filter {
     Name(Pattern),
     Path(Pattern),
     Type(FileType),
+    Extension(ExtensionSet),    // the extensions of file names
+    Dir(Pattern),               // directories matching the path pattern
+    Hidden,                     // the name starts with a dot
+    Ignored(IgnoreMatcher),     // ignored by ignore files
+    Git(GitStatus),             // the status in a Git repository
+    Content(ContentMatcher),    // search the contents of regular files
+    Text,                       // regular files that look like text
+    Binary,                     // regular files that do not look like text
+    Mime(Pattern),              // the MIME type of regular files
+    Interpreter(Pattern),       // the interpreter of scripts in the "#!" line
+    LinkName(Pattern),          // the contents of symlinks
+    TargetExists,               // symlinks to existing files
+    TargetInside(Vec<PathBuf>), // symlinks to files inside the starting points (real paths)
+    Links(Comparison),          // the number of hard links
+    Inode(Comparison),          // the inode number
+    SameFile(u64, u64),         // the device number and the inode number of a file
+    Empty(IgnoreMatcher),       // empty regular files and directories
     Chain(Chain),
     Action(Action), // always true; irreducible unless after short-circuit AND/OR
 }
@@ -38,6 +75,23 @@ impl std::fmt::Debug for Filter {
             Filter::Name(pattern) => write!(f, "Name({:?})", pattern),
             Filter::Path(pattern) => write!(f, "Path({:?})", pattern),
             Filter::Type(ftype) => write!(f, "Type({:?})", ftype),
+            Filter::Extension(extensions) => write!(f, "Extension({:?})", extensions),
+            Filter::Dir(pattern) => write!(f, "Dir({:?})", pattern),
+            Filter::Hidden => write!(f, "Hidden"),
+            Filter::Ignored(matcher) => write!(f, "Ignored({:?})", matcher),
+            Filter::Git(status) => write!(f, "Git({:?})", status),
+            Filter::Content(matcher) => write!(f, "Content({:?})", matcher),
+            Filter::Text => write!(f, "Text"),
+            Filter::Binary => write!(f, "Binary"),
+            Filter::Mime(pattern) => write!(f, "Mime({:?})", pattern),
+            Filter::Interpreter(pattern) => write!(f, "Interpreter({:?})", pattern),
+            Filter::LinkName(pattern) => write!(f, "LinkName({:?})", pattern),
+            Filter::TargetExists => write!(f, "TargetExists"),
+            Filter::TargetInside(dirs) => write!(f, "TargetInside({:?})", dirs),
+            Filter::Links(links) => write!(f, "Links({:?})", links),
+            Filter::Inode(inode) => write!(f, "Inode({:?})", inode),
+            Filter::SameFile(dev, ino) => write!(f, "SameFile({}, {})", dev, ino),
+            Filter::Empty(matcher) => write!(f, "Empty({:?})", matcher),
             Filter::Action(action) => write!(f, "Action({:?})", action),
             Filter::Chain(chain) => write!(f, "{:?}", chain),
         }
@@ -124,6 +178,13 @@ impl Chain {
     ) -> Result<Chain, Error> {
         let conf = parser::Config {
             unicode: config.unicode,
+            ignore_files: config.ignore_files.clone(),
+            real_roots: config
+                .includes
+                .iter()
+                .filter_map(|dir| dir.canonicalize().ok())
+                .collect(),
+            follow_symlink: config.follow_symlink,
         };
 
         parser::Parser::<'a>::new(args, conf)
@@ -219,6 +280,104 @@ impl Chain {
                         result,
                         self.test_filetype(ftype, entry) ^ link.negated,
                     ),
+                    Filter::Extension(ref extensions) => Chain::bool(
+                        &link.joint,
+                        result,
+                        entry
+                            .path
+                            .file_name()
+                            .map_or(false, |name| extensions.is_match(name))
+                            ^ link.negated,
+                    ),
+                    Filter::Dir(ref pattern) => Chain::bool(
+                        &link.joint,
+                        result,
+                        (self.test_filetype(&FileType::Directory, entry)
+                            && self.test_pattern(pattern, entry, true, config.match_full_path))
+                            ^ link.negated,
+                    ),
+                    Filter::Hidden => {
+                        Chain::bool(&link.joint, result, self.test_hidden(entry) ^ link.negated)
+                    }
+                    Filter::Ignored(ref matcher) => Chain::bool(
+                        &link.joint,
+                        result,
+                        self.test_ignored(matcher, entry) ^ link.negated,
+                    ),
+                    Filter::Git(ref status) => Chain::bool(
+                        &link.joint,
+                        result,
+                        self.test_git(*status, entry) ^ link.negated,
+                    ),
+                    Filter::Content(ref matcher) => Chain::bool(
+                        &link.joint,
+                        result,
+                        self.test_content(matcher, entry) ^ link.negated,
+                    ),
+                    Filter::Text => Chain::bool(
+                        &link.joint,
+                        result,
+                        (self.test_binary(entry, config) == Some(false)) ^ link.negated,
+                    ),
+                    Filter::Binary => Chain::bool(
+                        &link.joint,
+                        result,
+                        (self.test_binary(entry, config) == Some(true)) ^ link.negated,
+                    ),
+                    Filter::Mime(ref pattern) => Chain::bool(
+                        &link.joint,
+                        result,
+                        self.test_mime(pattern, entry, config) ^ link.negated,
+                    ),
+                    Filter::Interpreter(ref pattern) => Chain::bool(
+                        &link.joint,
+                        result,
+                        self.test_interpreter(pattern, entry) ^ link.negated,
+                    ),
+                    Filter::LinkName(ref pattern) => Chain::bool(
+                        &link.joint,
+                        result,
+                        self.test_link_name(pattern, entry) ^ link.negated,
+                    ),
+                    Filter::TargetExists => Chain::bool(
+                        &link.joint,
+                        result,
+                        self.test_target_exists(entry) ^ link.negated,
+                    ),
+                    Filter::TargetInside(ref dirs) => Chain::bool(
+                        &link.joint,
+                        result,
+                        self.test_target_inside(dirs, entry) ^ link.negated,
+                    ),
+                    Filter::Links(ref links) => Chain::bool(
+                        &link.joint,
+                        result,
+                        entry
+                            .metadata(config)
+                            .map_or(false, |meta| links.test(meta.nlink()))
+                            ^ link.negated,
+                    ),
+                    Filter::Inode(ref inode) => Chain::bool(
+                        &link.joint,
+                        result,
+                        entry
+                            .metadata(config)
+                            .map_or(false, |meta| inode.test(meta.ino()))
+                            ^ link.negated,
+                    ),
+                    Filter::SameFile(ref dev, ref ino) => Chain::bool(
+                        &link.joint,
+                        result,
+                        entry
+                            .metadata(config)
+                            .map_or(false, |meta| meta.dev() == *dev && meta.ino() == *ino)
+                            ^ link.negated,
+                    ),
+                    Filter::Empty(ref matcher) => Chain::bool(
+                        &link.joint,
+                        result,
+                        self.test_empty(matcher, entry, config) ^ link.negated,
+                    ),
                     Filter::Chain(ref chain) => Chain::bool(
                         &link.joint,
                         result,
@@ -306,6 +465,197 @@ impl Chain {
         }
     }
 
+    fn test_hidden(&self, entry: &DirEntry) -> bool {
+        entry
+            .path
+            .file_name()
+            .map_or(false, |name| name.as_bytes().starts_with(b"."))
+    }
+
+    fn test_ignored(&self, matcher: &IgnoreMatcher, entry: &DirEntry) -> bool {
+        let is_dir = entry.file_type.map_or(false, |ftype| ftype.is_dir());
+
+        matcher.is_ignored(entry.path, is_dir)
+    }
+
+    fn test_git(&self, status: GitStatus, entry: &DirEntry) -> bool {
+        let is_dir = entry.file_type.map_or(false, |ftype| ftype.is_dir());
+
+        git::test_status(entry.path, is_dir, status)
+    }
+
+    // Symlinks are not followed unless they are traversed by the walker.
+    fn test_content(&self, matcher: &ContentMatcher, entry: &DirEntry) -> bool {
+        if !entry.file_type.map_or(false, |ftype| ftype.is_file()) {
+            return false;
+        }
+
+        matcher.is_match(entry.path).unwrap_or_else(|err| {
+            warn(&format!(
+                "could not read {:?}: {}",
+                entry.path.as_os_str(),
+                err
+            ));
+            false
+        })
+    }
+
+    // None for anything other than a readable regular file.
+    fn test_binary(&self, entry: &DirEntry, config: &AppOptions) -> Option<bool> {
+        if !entry.file_type.map_or(false, |ftype| ftype.is_file()) {
+            return None;
+        }
+
+        match is_binary(entry.path, config.sniff_size) {
+            Ok(binary) => Some(binary),
+            Err(err) => {
+                warn(&format!(
+                    "could not read {:?}: {}",
+                    entry.path.as_os_str(),
+                    err
+                ));
+                None
+            }
+        }
+    }
+
+    fn test_mime(&self, pattern: &Pattern, entry: &DirEntry, config: &AppOptions) -> bool {
+        if !entry.file_type.map_or(false, |ftype| ftype.is_file()) {
+            return false;
+        }
+
+        match mime_type(entry.path, config.sniff_size) {
+            Ok(mime) => pattern.is_match(mime),
+            Err(err) => {
+                warn(&format!(
+                    "could not read {:?}: {}",
+                    entry.path.as_os_str(),
+                    err
+                ));
+                false
+            }
+        }
+    }
+
+    // Scripts are regular files or symlinks to them, like executables of FileType::Executable.
+    fn test_interpreter(&self, pattern: &Pattern, entry: &DirEntry) -> bool {
+        let is_script = match entry.file_type {
+            Some(ref file_type) if file_type.is_file() => true,
+            // entry.path.metadata() always follows symlinks
+            Some(ref file_type) if file_type.is_symlink() => {
+                entry.path.metadata().map_or(false, |meta| meta.is_file())
+            }
+            _ => false,
+        };
+
+        if !is_script {
+            return false;
+        }
+
+        match interpreter(entry.path) {
+            Ok(Some(name)) => pattern.is_match(OsStr::from_bytes(&name)),
+            Ok(None) => false,
+            Err(err) => {
+                warn(&format!(
+                    "could not read {:?}: {}",
+                    entry.path.as_os_str(),
+                    err
+                ));
+                false
+            }
+        }
+    }
+
+    // Directories are empty if nothing in them would be searched: hidden entries are not
+    // counted without --all, and ignored entries are not counted without --no-ignore,
+    // including those only ignored by the files specified with --ignore-file.
+    fn test_empty(&self, matcher: &IgnoreMatcher, entry: &DirEntry, config: &AppOptions) -> bool {
+        let meta = match entry.metadata(config) {
+            Some(meta) => meta,
+            None => return false,
+        };
+
+        if meta.is_file() {
+            return meta.len() == 0;
+        }
+        if !meta.is_dir() {
+            return false;
+        }
+
+        let mut children = match fs::read_dir(entry.path) {
+            Ok(children) => children,
+            Err(err) => {
+                warn(&format!(
+                    "could not read directory {:?}: {}",
+                    entry.path.as_os_str(),
+                    err
+                ));
+                return false;
+            }
+        };
+
+        // stop at the first entry counted
+        !children.any(|child| {
+            let child = match child {
+                Ok(child) => child,
+                Err(_) => return true,
+            };
+
+            if !config.dot_files && child.file_name().as_bytes().starts_with(b".") {
+                return false;
+            }
+            if config.read_ignore {
+                let is_dir = child.file_type().map_or(false, |ftype| ftype.is_dir());
+
+                return !matcher.is_ignored(&entry.path.join(child.file_name()), is_dir);
+            }
+
+            true
+        })
+    }
+
+    fn is_symlink(&self, entry: &DirEntry) -> bool {
+        entry.file_type.map_or(false, |ftype| ftype.is_symlink())
+    }
+
+    fn test_link_name(&self, pattern: &Pattern, entry: &DirEntry) -> bool {
+        if !self.is_symlink(entry) {
+            return false;
+        }
+
+        match entry.path.read_link() {
+            Ok(target) => pattern.is_match(target),
+            Err(err) => {
+                warn(&format!(
+                    "could not read symlink {:?}: {}",
+                    entry.path.as_os_str(),
+                    err
+                ));
+                false
+            }
+        }
+    }
+
+    // entry.path.metadata() always follows symlinks
+    fn test_target_exists(&self, entry: &DirEntry) -> bool {
+        self.is_symlink(entry) && entry.path.metadata().is_ok()
+    }
+
+    fn test_target_inside(&self, dirs: &[PathBuf], entry: &DirEntry) -> bool {
+        if !self.is_symlink(entry) {
+            return false;
+        }
+
+        is_target_inside(entry.path, dirs).unwrap_or_else(|err| {
+            warn(&format!(
+                "could not resolve symlink {:?}: {}",
+                entry.path.as_os_str(),
+                err
+            ));
+            false
+        })
+    }
+
     #[cfg(test)]
     fn test_logic(&self) -> bool {
         self.links
@@ -1,3 +1,8 @@
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use super::super::regex::bytes::RegexBuilder;
+
 use super::*;
 
 // Operator in order of decreasing precedence:
@@ -17,12 +22,36 @@ use super::*;
 // * regex <regex pattern>  # match the absolute/relative path, e.g. regex '/[^/]*\.rs$'
 // * iregex <regex pattern>
 // * type <file type[,file type]...>
+// * ext <extension[,extension]...>   # e.g. ext rs,toml,tar.gz
+// * iext <extension[,extension]...>
+// * dir <glob pattern>     # match the absolute/relative path of directories
+// * hidden                 # the name starts with a dot
+// * ignored                # ignored by .(git)ignore files, .ffignore files, etc.
+// * tracked                # in the index of a Git repository
+// * untracked
+// * modified               # changed in the working tree but not staged
+// * staged                 # changed in the index since the last commit
+// * contains <string>      # search the contents of regular files
+// * grep <regex pattern>   # search the contents of regular files line by line
+// * text                   # regular files that look like text (see --sniff-size)
+// * binary
+// * mime <glob pattern>    # match the MIME type of regular files, e.g. mime 'image/*'
+// * interpreter <glob pattern>  # match the interpreter of scripts, e.g. interpreter 'python*'
+// * lname <glob pattern>   # match the contents of symlinks, e.g. lname '/opt/*'
+// * ilname <glob pattern>
+// * target-exists          # symlinks to existing files
+// * target-inside          # symlinks to files inside the starting points
+// * links [+-]<number>     # the number of hard links (more than, less than, exactly)
+// * inum [+-]<number>      # the inode number
+// * samefile <path>        # hard links to the same file
+// * empty                  # empty regular files and directories
 // * prune                  # do not descend into a directory
 // * quit                   # stop searching but not instantly due to multi-threading
 // * true
 // * false
 // * print                  # unaffected by the --print0 flag
 // * print0
+// * ls                     # print the result in long listing format like `find -ls`
 // * ...
 // The head of an expression is case-insensitive.
 
@@ -31,6 +60,9 @@ const MAX_DEPTH: u8 = 8;
 
 pub struct Config {
     pub unicode: bool,
+    pub ignore_files: Vec<PathBuf>,
+    pub real_roots: Vec<PathBuf>, // the starting points with symlinks resolved
+    pub follow_symlink: bool,
 }
 
 enum Token<'a> {
@@ -135,8 +167,6 @@ impl<'a, Iter: Iterator<Item = &'a OsStr>> Parser<'a, Iter> {
         }
     }
 
-    // IDEA: Dir(Pattern) = Type(Directory) & Path(Pattern)
-    //       Ext(EXT) = Name(?*.EXT)
     fn parse_predicate(&mut self, depth: u8, token: &'a OsStr) -> Result<Chain, Error> {
         macro_rules! tok (($($anything:tt)+) => ({
             self.tokens.push(Token::Txt(token));
@@ -167,16 +197,45 @@ impl<'a, Iter: Iterator<Item = &'a OsStr>> Parser<'a, Iter> {
                 }
                 b"not" | b"!" => tok!(self.parse_expr(depth, MAX_RANK).map(|c| c.not())),
                 b"type" => tok!(self.parse_file_type()),
+                b"ext" => tok!(self.parse_extensions(false)),
+                b"iext" => tok!(self.parse_extensions(true)),
+                b"dir" => tok!(self.parse_dir_glob()),
                 b"name" => tok!(self.parse_name_glob(false)),
                 b"iname" => tok!(self.parse_name_glob(true)),
                 b"path" => tok!(self.parse_path_glob(false)),
                 b"ipath" => tok!(self.parse_path_glob(true)),
                 b"regex" => tok!(self.parse_regex(false)),
                 b"iregex" => tok!(self.parse_regex(true)),
+                b"hidden" => tok!(Ok(Chain::new(Filter::Hidden, false))),
+                b"ignored" => tok!(IgnoreMatcher::new(&self.config.ignore_files)
+                    .map(|matcher| Chain::new(Filter::Ignored(matcher), false))),
+                b"tracked" => tok!(Ok(Chain::new(Filter::Git(GitStatus::Tracked), false))),
+                b"untracked" => tok!(Ok(Chain::new(Filter::Git(GitStatus::Untracked), false))),
+                b"modified" => tok!(Ok(Chain::new(Filter::Git(GitStatus::Modified), false))),
+                b"staged" => tok!(Ok(Chain::new(Filter::Git(GitStatus::Staged), false))),
+                b"contains" => tok!(self.parse_content(false)),
+                b"grep" => tok!(self.parse_content(true)),
+                b"text" => tok!(Ok(Chain::new(Filter::Text, false))),
+                b"binary" => tok!(Ok(Chain::new(Filter::Binary, false))),
+                b"mime" => tok!(self.parse_mime_glob()),
+                b"interpreter" => tok!(self.parse_interpreter_glob()),
+                b"links" => tok!(self.parse_comparison(Filter::Links)),
+                b"inum" => tok!(self.parse_comparison(Filter::Inode)),
+                b"samefile" => tok!(self.parse_same_file()),
+                b"empty" => tok!(IgnoreMatcher::new(&self.config.ignore_files)
+                    .map(|matcher| Chain::new(Filter::Empty(matcher), false))),
+                b"lname" => tok!(self.parse_link_glob(false)),
+                b"ilname" => tok!(self.parse_link_glob(true)),
+                b"target-exists" => tok!(Ok(Chain::new(Filter::TargetExists, false))),
+                b"target-inside" => tok!(Ok(Chain::new(
+                    Filter::TargetInside(self.config.real_roots.clone()),
+                    false
+                ))),
                 b"true" => tok!(Ok(Chain::new(Filter::Anything, false))),
                 b"false" => tok!(Ok(Chain::new(Filter::Anything, true))),
                 b"print" => tok!(Ok(Chain::new(Filter::Action(Action::Print), false))),
                 b"print0" => tok!(Ok(Chain::new(Filter::Action(Action::Print0), false))),
+                b"ls" => tok!(Ok(Chain::new(Filter::Action(Action::Ls), false))),
                 b"prune" => tok!(Ok(Chain::new(Filter::Action(Action::Prune), false))),
                 b"quit" => tok!(Ok(Chain::new(Filter::Action(Action::Quit), false))),
                 _ => tok!(Err(Error::from_str(&format!(
@@ -259,6 +318,35 @@ impl<'a, Iter: Iterator<Item = &'a OsStr>> Parser<'a, Iter> {
         })
     }
 
+    fn parse_extensions(&mut self, case_insensitive: bool) -> Result<Chain, Error> {
+        self.next(None, "expected an extension").and_then(|token| {
+            self.tokens.push(Token::Txt(token));
+
+            ExtensionSet::new(&token.split_at_comma(), case_insensitive)
+                .map(|extensions| Chain::new(Filter::Extension(extensions), false))
+        })
+    }
+
+    fn parse_dir_glob(&mut self) -> Result<Chain, Error> {
+        self.next(None, "expected a glob pattern")
+            .and_then(|token| {
+                self.tokens.push(Token::Raw(token));
+
+                PatternBuilder::new(token)
+                    .use_regex(false)
+                    .unicode(self.config.unicode)
+                    .match_full_path(true)
+                    .build()
+                    .map(|pattern| Chain::new(Filter::Dir(pattern), false))
+                    .map_err(|err| {
+                        Error::from_str(&format!(
+                            "failed to build glob pattern {:?}:\n{}",
+                            token, err
+                        ))
+                    })
+            })
+    }
+
     fn parse_name_glob(&mut self, case_insensitive: bool) -> Result<Chain, Error> {
         self.next(None, "expected a glob pattern")
             .and_then(|token| {
@@ -321,6 +409,128 @@ impl<'a, Iter: Iterator<Item = &'a OsStr>> Parser<'a, Iter> {
                     })
             })
     }
+
+    fn parse_mime_glob(&mut self) -> Result<Chain, Error> {
+        self.next(None, "expected a glob pattern")
+            .and_then(|token| {
+                self.tokens.push(Token::Raw(token));
+
+                PatternBuilder::new(token)
+                    .use_regex(false)
+                    .case_insensitive(true)
+                    .match_full_path(false)
+                    .build()
+                    .map(|pattern| Chain::new(Filter::Mime(pattern), false))
+                    .map_err(|err| {
+                        Error::from_str(&format!(
+                            "failed to build glob pattern {:?}:\n{}",
+                            token, err
+                        ))
+                    })
+            })
+    }
+
+    fn parse_interpreter_glob(&mut self) -> Result<Chain, Error> {
+        self.next(None, "expected a glob pattern")
+            .and_then(|token| {
+                self.tokens.push(Token::Raw(token));
+
+                PatternBuilder::new(token)
+                    .use_regex(false)
+                    .unicode(self.config.unicode)
+                    .match_full_path(false)
+                    .build()
+                    .map(|pattern| Chain::new(Filter::Interpreter(pattern), false))
+                    .map_err(|err| {
+                        Error::from_str(&format!(
+                            "failed to build glob pattern {:?}:\n{}",
+                            token, err
+                        ))
+                    })
+            })
+    }
+
+    fn parse_link_glob(&mut self, case_insensitive: bool) -> Result<Chain, Error> {
+        self.next(None, "expected a glob pattern")
+            .and_then(|token| {
+                self.tokens.push(Token::Raw(token));
+
+                PatternBuilder::new(token)
+                    .use_regex(false)
+                    .unicode(self.config.unicode)
+                    .case_insensitive(case_insensitive)
+                    .match_full_path(false)
+                    .build()
+                    .map(|pattern| Chain::new(Filter::LinkName(pattern), false))
+                    .map_err(|err| {
+                        Error::from_str(&format!(
+                            "failed to build glob pattern {:?}:\n{}",
+                            token, err
+                        ))
+                    })
+            })
+    }
+
+    fn parse_comparison(&mut self, filter: fn(Comparison) -> Filter) -> Result<Chain, Error> {
+        self.next(None, "expected a number").and_then(|token| {
+            self.tokens.push(Token::Txt(token));
+
+            Comparison::from_str(token).map(|comparison| Chain::new(filter(comparison), false))
+        })
+    }
+
+    // The reference file is resolved once here instead of for every file tested.
+    fn parse_same_file(&mut self) -> Result<Chain, Error> {
+        self.next(None, "expected a path").and_then(|token| {
+            self.tokens.push(Token::Raw(token));
+
+            let path = Path::new(token);
+            let meta = if self.config.follow_symlink {
+                path.metadata()
+            } else {
+                path.symlink_metadata()
+            };
+
+            meta.map(|meta| Chain::new(Filter::SameFile(meta.dev(), meta.ino()), false))
+                .map_err(|err| Error::from_str(&format!("could not access {:?}: {}", token, err)))
+        })
+    }
+
+    fn parse_content(&mut self, use_regex: bool) -> Result<Chain, Error> {
+        let errmsg = if use_regex {
+            "expected a regex pattern"
+        } else {
+            "expected a string"
+        };
+
+        self.next(None, errmsg).and_then(|token| {
+            self.tokens.push(Token::Raw(token));
+
+            if !use_regex {
+                let literal = token.as_bytes().to_vec();
+
+                return Ok(Chain::new(
+                    Filter::Content(ContentMatcher::Literal(literal)),
+                    false,
+                ));
+            }
+
+            let pattern = token.to_str().ok_or_else(|| {
+                Error::from_str(&format!("need a UTF-8 encoded regex pattern {:?}", token))
+            })?;
+
+            RegexBuilder::new(pattern)
+                .unicode(self.config.unicode)
+                .build()
+                .map(|regex| Chain::new(Filter::Content(ContentMatcher::Regex(regex)), false))
+                .map_err(|err| {
+                    Error::from_str(&format!(
+                        "failed to build regex pattern {:?}:\n{}",
+                        token, err
+                    ))
+                })
+        })
+    }
 }
 
 #[cfg(test)]
@@ -335,9 +545,17 @@ mod tests {
     macro_rules! calc {
         ($expected:expr, $args:expr) => {
             let errmsg = format!("Valid: {:?}", $args);
-            let filter = Parser::new(&mut $args.into_iter(), Config { unicode: false })
-                .parse()
-                .unwrap_or_else(|err| panic!("\n{}\n{}\n", errmsg, err));
+            let filter = Parser::new(
+                &mut $args.into_iter(),
+                Config {
+                    unicode: false,
+                    ignore_files: vec![],
+                    real_roots: vec![],
+                    follow_symlink: false,
+                },
+            )
+            .parse()
+            .unwrap_or_else(|err| panic!("\n{}\n{}\n", errmsg, err));
             let errmsg = format!("{}\nFilter: {:?}", errmsg, filter);
             assert_eq!($expected, filter.test_logic(), "\n{}\n", errmsg);
         };
@@ -346,9 +564,17 @@ mod tests {
     macro_rules! fail {
         ($args:expr) => {
             let errmsg = format!("\nInvalid: {:?}\n", $args);
-            Parser::new(&mut $args.into_iter(), Config { unicode: false })
-                .parse()
-                .expect_err(&errmsg);
+            Parser::new(
+                &mut $args.into_iter(),
+                Config {
+                    unicode: false,
+                    ignore_files: vec![],
+                    real_roots: vec![],
+                    follow_symlink: false,
+                },
+            )
+            .parse()
+            .expect_err(&errmsg);
         };
     }
 
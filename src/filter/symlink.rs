@@ -0,0 +1,29 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::super::fshelper::{logical_path, real_path};
+
+// Resolve the target of a symlink fully. The target of a broken symlink is resolved
+// lexically against the real path of the directory containing the symlink.
+pub fn resolve_target(path: &Path) -> io::Result<PathBuf> {
+    match path.canonicalize() {
+        Ok(target) => Ok(target),
+        Err(_) => {
+            let target = fs::read_link(path)?;
+            let link = real_path(path)?;
+
+            match link.parent() {
+                Some(dir) => logical_path(&dir.join(target)),
+                None => logical_path(&target),
+            }
+        }
+    }
+}
+
+// Whether the target of a symlink is inside any of the directories (real paths).
+pub fn is_target_inside(path: &Path, dirs: &[PathBuf]) -> io::Result<bool> {
+    let target = resolve_target(path)?;
+
+    Ok(dirs.iter().any(|dir| target.starts_with(dir)))
+}
@@ -1,9 +1,18 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::env;
+use std::ffi::{CStr, OsStr, OsString};
 use std::fs;
-use std::io;
+use std::io::{self, Read};
+use std::mem;
+use std::os::unix::ffi::OsStrExt;
 use std::os::unix::fs::PermissionsExt;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::ptr;
+use std::sync::Mutex;
 
+use super::nix::libc;
 use super::same_file::is_same_file;
 
 lazy_static! {
@@ -26,15 +35,38 @@ lazy_static! {
         }).unwrap()
     };
     static ref HAS_PWD: bool = !(*PWD).as_os_str().is_empty();
+    static ref USERS: Mutex<HashMap<u32, Option<OsString>>> = Mutex::new(HashMap::new());
+    static ref GROUPS: Mutex<HashMap<u32, Option<OsString>>> = Mutex::new(HashMap::new());
+}
+
+// The most directories cached by each thread for real_path().
+const REAL_DIRS_LIMIT: usize = 4096;
+
+thread_local! {
+    // Each thread has its own cache, so that no lock is needed for every search result.
+    static REAL_DIRS: RefCell<HashMap<PathBuf, PathBuf>> = RefCell::new(HashMap::new());
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathResolution {
+    Unchanged,
+    Real,    // resolve symlinks as encountered (physical)
+    Logical, // resolve ".." components before symlinks
+}
+
+// Resolve the path into an absolute path, or leave it unchanged on failure.
+pub fn resolve_path(path: &Path, resolution: PathResolution) -> Cow<'_, Path> {
+    match resolution {
+        PathResolution::Unchanged => Cow::Borrowed(path),
+        PathResolution::Real => real_path(path).map_or(Cow::Borrowed(path), Cow::Owned),
+        PathResolution::Logical => logical_path(path).map_or(Cow::Borrowed(path), Cow::Owned),
+    }
 }
 
 pub fn to_absolute_path(path: &Path) -> io::Result<PathBuf> {
-    // IDEA: Provide a flag --real-path for canonicalization of file path?
-    //       Match real paths and/or output real paths? (affect --include and --exclude?)
-    //       Logical: resolve '..' components before symlinks (Windows)
-    //       Physical: resolve symlinks as encountered (Unix)
     // NOTE: A path like /root/../compo is considered an absolute path, seriously.
     //       An absolute path is not always a real path (with symlinks fully resolved).
+    //       See real_path() and logical_path().
     if path.is_absolute() {
         Ok(path.to_path_buf())
     } else {
@@ -51,6 +83,80 @@ pub fn to_absolute_path(path: &Path) -> io::Result<PathBuf> {
     }
 }
 
+// Resolve symlinks in the directory part of the path. The last component is kept so that
+// a symlink still refers to itself. Resolved directories are cached for the search results
+// in the same directory.
+pub fn real_path(path: &Path) -> io::Result<PathBuf> {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => {
+            let parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+
+            let cached = REAL_DIRS.with(|dirs| dirs.borrow().get(parent).map(|dir| dir.join(name)));
+
+            if let Some(path) = cached {
+                return Ok(path);
+            }
+
+            let dir = parent.canonicalize()?;
+            let path = dir.join(name);
+
+            REAL_DIRS.with(|dirs| {
+                let mut dirs = dirs.borrow_mut();
+
+                // the directories of the search results are mostly visited one by one
+                if dirs.len() >= REAL_DIRS_LIMIT {
+                    dirs.clear();
+                }
+                dirs.insert(parent.to_path_buf(), dir);
+            });
+
+            Ok(path)
+        }
+        _ => path.canonicalize(), // e.g. "/", "." or "a/.."
+    }
+}
+
+// Resolve "." and ".." components lexically without resolving symlinks.
+pub fn logical_path(path: &Path) -> io::Result<PathBuf> {
+    let mut logical = PathBuf::new();
+
+    for compo in to_absolute_path(path)?.components() {
+        match compo {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                logical.pop();
+            }
+            _ => logical.push(compo.as_os_str()),
+        }
+    }
+
+    Ok(logical)
+}
+
+// Read a list of paths from a file or from stdin if the file name is "-". The paths are
+// delimited by NUL if there is any, otherwise by LF. Empty paths are ignored.
+pub fn read_path_list(file: &OsStr) -> io::Result<Vec<PathBuf>> {
+    let mut bytes = Vec::new();
+
+    if file == "-" {
+        io::stdin().lock().read_to_end(&mut bytes)?;
+    } else {
+        fs::File::open(file)?.read_to_end(&mut bytes)?;
+    }
+
+    let delimiter = if bytes.contains(&b'\0') { b'\0' } else { b'\n' };
+
+    Ok(bytes
+        .split(|&byte| byte == delimiter)
+        .filter(|path| !path.is_empty())
+        .map(|path| PathBuf::from(OsStr::from_bytes(path)))
+        .collect())
+}
+
 // Path::exists() and Path::is_dir() do not behave intuitively for "." and ".."
 // See: https://github.com/rust-lang/rust/issues/45302
 pub fn exists(path: &Path) -> bool {
@@ -64,3 +170,75 @@ pub fn exists(path: &Path) -> bool {
 pub fn is_executable(meta: &fs::Metadata) -> bool {
     meta.permissions().mode() & 0o111 != 0
 }
+
+// Look up the login name of a user in the password database.
+pub fn user_name(uid: u32) -> Option<OsString> {
+    let mut users = USERS.lock().unwrap();
+
+    users
+        .entry(uid)
+        .or_insert_with(|| {
+            lookup_name(|buffer, found| unsafe {
+                let mut pwd: libc::passwd = mem::zeroed();
+                let mut result = ptr::null_mut();
+                let errno = libc::getpwuid_r(
+                    uid,
+                    &mut pwd,
+                    buffer.as_mut_ptr(),
+                    buffer.len(),
+                    &mut result,
+                );
+                if errno == 0 && !result.is_null() {
+                    *found = Some(os_string_from_ptr(pwd.pw_name));
+                }
+                errno
+            })
+        })
+        .clone()
+}
+
+// Look up the name of a group in the group database.
+pub fn group_name(gid: u32) -> Option<OsString> {
+    let mut groups = GROUPS.lock().unwrap();
+
+    groups
+        .entry(gid)
+        .or_insert_with(|| {
+            lookup_name(|buffer, found| unsafe {
+                let mut grp: libc::group = mem::zeroed();
+                let mut result = ptr::null_mut();
+                let errno = libc::getgrgid_r(
+                    gid,
+                    &mut grp,
+                    buffer.as_mut_ptr(),
+                    buffer.len(),
+                    &mut result,
+                );
+                if errno == 0 && !result.is_null() {
+                    *found = Some(os_string_from_ptr(grp.gr_name));
+                }
+                errno
+            })
+        })
+        .clone()
+}
+
+fn lookup_name<F>(mut lookup: F) -> Option<OsString>
+where
+    F: FnMut(&mut Vec<libc::c_char>, &mut Option<OsString>) -> libc::c_int,
+{
+    let mut buffer = vec![0; 1024];
+    let mut found = None;
+
+    // retry with a larger buffer until the record fits in
+    while lookup(&mut buffer, &mut found) == libc::ERANGE && buffer.len() < 0x100000 {
+        let size = buffer.len() * 2;
+        buffer.resize(size, 0);
+    }
+
+    found
+}
+
+unsafe fn os_string_from_ptr(ptr: *const libc::c_char) -> OsString {
+    OsStr::from_bytes(CStr::from_ptr(ptr).to_bytes()).to_os_string()
+}
@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::ObjectId;
+
+// https://git-scm.com/docs/index-format
+#[derive(Debug)]
+pub struct IndexEntry {
+    pub mtime: (u32, u32), // seconds, nanoseconds
+    pub mode: u32,
+    pub size: u32, // truncated to 32 bits
+    pub id: ObjectId,
+    pub stage: u8, // non-zero for unmerged paths
+}
+
+// The staging area of a repository. The paths are relative to the working tree.
+pub struct Index {
+    pub entries: BTreeMap<Vec<u8>, IndexEntry>,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> io::Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + count)
+            .ok_or_else(|| invalid("index file is truncated"))?;
+
+        self.position += count;
+
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        self.bytes(2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        self.bytes(4)
+            .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    // the variable-width integer used by the path compression of version 4
+    fn offset(&mut self) -> io::Result<usize> {
+        let mut byte = self.bytes(1)?[0];
+        let mut value = (byte & 0x7F) as usize;
+
+        while byte & 0x80 != 0 {
+            if value >= 1 << 56 {
+                return Err(invalid("found invalid path in index file"));
+            }
+            byte = self.bytes(1)?[0];
+            value = ((value + 1) << 7) | (byte & 0x7F) as usize;
+        }
+
+        Ok(value)
+    }
+
+    fn until_nul(&mut self) -> io::Result<&'a [u8]> {
+        let rest = &self.data[self.position..];
+        let len = rest
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or_else(|| invalid("index file is truncated"))?;
+
+        self.position += len + 1;
+
+        Ok(&rest[..len])
+    }
+}
+
+impl Index {
+    pub fn empty() -> Index {
+        Index {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    pub fn read(path: &Path) -> io::Result<Index> {
+        match fs::read(path) {
+            Ok(data) => Index::parse(&data),
+            // a new repository has no index
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(Index::empty()),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn parse(data: &[u8]) -> io::Result<Index> {
+        let mut reader = Reader { data, position: 0 };
+
+        if reader.bytes(4)? != b"DIRC" {
+            return Err(invalid("found invalid signature of index file"));
+        }
+
+        let version = reader.u32()?;
+
+        if !(2..=4).contains(&version) {
+            return Err(invalid("found unsupported version of index file"));
+        }
+
+        let count = reader.u32()?;
+        let mut entries = BTreeMap::new();
+        let mut path: Vec<u8> = Vec::new();
+
+        for _ in 0..count {
+            let start = reader.position;
+
+            reader.bytes(8)?; // ctime
+            let mtime = (reader.u32()?, reader.u32()?);
+            reader.bytes(8)?; // dev, ino
+            let mode = reader.u32()?;
+            reader.bytes(8)?; // uid, gid
+            let size = reader.u32()?;
+            let id = ObjectId::from_bytes(reader.bytes(20)?);
+            let flags = reader.u16()?;
+
+            if version >= 3 && flags & 0x4000 != 0 {
+                reader.bytes(2)?; // extended flags
+            }
+
+            if version == 4 {
+                let remove = reader.offset()?;
+
+                if remove > path.len() {
+                    return Err(invalid("found invalid path in index file"));
+                }
+
+                let len = path.len() - remove;
+
+                path.truncate(len);
+                path.extend_from_slice(reader.until_nul()?);
+            } else {
+                path = reader.until_nul()?.to_vec();
+
+                // entries are padded with NULs to a multiple of eight bytes
+                let len = reader.position - start;
+
+                reader.bytes((8 - len % 8) % 8)?;
+            }
+
+            let entry = IndexEntry {
+                mtime,
+                mode,
+                size,
+                id,
+                stage: ((flags >> 12) & 0x3) as u8,
+            };
+
+            // keep the first entry of unmerged paths
+            entries.entry(path.clone()).or_insert(entry);
+        }
+
+        Ok(Index { entries })
+    }
+
+    pub fn get(&self, path: &[u8]) -> Option<&IndexEntry> {
+        self.entries.get(path)
+    }
+
+    // Whether any path in the directory is in the index.
+    pub fn contains_dir(&self, path: &[u8]) -> bool {
+        let mut prefix = path.to_vec();
+
+        prefix.push(b'/');
+        self.entries
+            .range(prefix.clone()..)
+            .next()
+            .map_or(false, |(path, _)| path.starts_with(&prefix))
+    }
+}
@@ -0,0 +1,351 @@
+mod index;
+mod object;
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::fshelper::logical_path;
+use super::internal::warn;
+use super::sha1_smol::Sha1;
+
+use self::index::{Index, IndexEntry};
+use self::object::ObjectStore;
+
+// The status of files in Git repositories, read from the repositories directly.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GitStatus {
+    Tracked,   // in the index
+    Untracked, // in a working tree but not in the index
+    Modified,  // different from the index
+    Staged,    // different between the index and HEAD
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObjectId(pub [u8; 20]);
+
+impl std::fmt::Debug for ObjectId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+impl ObjectId {
+    pub fn from_bytes(bytes: &[u8]) -> ObjectId {
+        let mut id = [0; 20];
+
+        id.copy_from_slice(bytes);
+        ObjectId(id)
+    }
+
+    pub fn from_hex(hex: &[u8]) -> Option<ObjectId> {
+        if hex.len() != 40 {
+            return None;
+        }
+
+        let mut id = [0; 20];
+
+        for (i, byte) in id.iter_mut().enumerate() {
+            let digits = std::str::from_utf8(&hex[i * 2..i * 2 + 2]).ok()?;
+
+            *byte = u8::from_str_radix(digits, 16).ok()?;
+        }
+
+        Some(ObjectId(id))
+    }
+
+    pub fn to_hex(self) -> String {
+        self.0.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
+
+// The files of a tree with their modes and IDs.
+type Tree = HashMap<Vec<u8>, (u32, ObjectId)>;
+
+struct Repository {
+    work_dir: PathBuf,
+    git_dir: PathBuf,
+    common_dir: PathBuf, // shared by linked working trees
+    index: Index,
+    objects: ObjectStore,
+    head: Mutex<Option<Arc<Option<Tree>>>>, // None inside if unreadable
+}
+
+lazy_static! {
+    // The repository of each directory.
+    static ref REPOS: Mutex<HashMap<PathBuf, Option<Arc<Repository>>>> = Mutex::new(HashMap::new());
+}
+
+// Find the repository of which the working tree contains the directory.
+fn find_repository(dir: &Path) -> Option<Arc<Repository>> {
+    if let Some(repo) = REPOS.lock().unwrap().get(dir) {
+        return repo.clone();
+    }
+
+    let repo = match Repository::open(dir) {
+        Some(repo) => Some(Arc::new(repo)),
+        None => dir.parent().and_then(find_repository),
+    };
+
+    REPOS
+        .lock()
+        .unwrap()
+        .insert(dir.to_path_buf(), repo.clone());
+
+    repo
+}
+
+impl Repository {
+    fn open(dir: &Path) -> Option<Repository> {
+        let dot_git = dir.join(".git");
+        let git_dir = if dot_git.is_dir() {
+            dot_git
+        } else if dot_git.is_file() {
+            // linked working trees and submodules
+            let text = fs::read(&dot_git).ok()?;
+            let text = text.strip_suffix_newline();
+
+            if !text.starts_with(b"gitdir: ") {
+                return None;
+            }
+            dir.join(Path::new(std::ffi::OsStr::from_bytes(&text[8..])))
+        } else {
+            return None;
+        };
+
+        if !git_dir.join("HEAD").is_file() {
+            return None;
+        }
+
+        let common_dir = match fs::read(git_dir.join("commondir")) {
+            Ok(text) => git_dir.join(Path::new(std::ffi::OsStr::from_bytes(
+                text.strip_suffix_newline(),
+            ))),
+            Err(_) => git_dir.clone(),
+        };
+        let index = Index::read(&git_dir.join("index")).unwrap_or_else(|err| {
+            warn(&format!(
+                "failed to read Git index in {:?}: {}",
+                git_dir, err
+            ));
+            Index::empty()
+        });
+        let objects = ObjectStore::new(&common_dir.join("objects"));
+
+        Some(Repository {
+            work_dir: dir.to_path_buf(),
+            git_dir,
+            common_dir,
+            index,
+            objects,
+            head: Mutex::new(None),
+        })
+    }
+
+    fn resolve_ref(&self, name: &[u8], depth: u8) -> io::Result<Option<ObjectId>> {
+        if depth > 5 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "found too many levels of symbolic references",
+            ));
+        }
+
+        let path = Path::new(std::ffi::OsStr::from_bytes(name));
+        let dir = if name == b"HEAD" {
+            &self.git_dir
+        } else {
+            &self.common_dir
+        };
+
+        match fs::read(dir.join(path)) {
+            Ok(text) => {
+                let text = text.strip_suffix_newline();
+
+                if text.starts_with(b"ref: ") {
+                    self.resolve_ref(&text[5..], depth + 1)
+                } else {
+                    Ok(ObjectId::from_hex(text))
+                }
+            }
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                let packed = match fs::read(self.common_dir.join("packed-refs")) {
+                    Ok(packed) => packed,
+                    Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+                    Err(err) => return Err(err),
+                };
+
+                // lines of "<object id> <ref name>"
+                Ok(packed
+                    .split(|&byte| byte == b'\n')
+                    .find(|line| line.len() == 41 + name.len() && line.ends_with(name))
+                    .and_then(|line| ObjectId::from_hex(&line[..40])))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn read_head(&self) -> io::Result<Tree> {
+        let mut tree = HashMap::new();
+
+        // no commit yet
+        if let Some(commit) = self.resolve_ref(b"HEAD", 0)? {
+            let id = self.objects.commit_tree(&commit)?;
+
+            self.objects.read_tree(&id, b"", &mut tree)?;
+        }
+
+        Ok(tree)
+    }
+
+    fn head(&self) -> Arc<Option<Tree>> {
+        let mut head = self.head.lock().unwrap();
+
+        Arc::clone(head.get_or_insert_with(|| {
+            Arc::new(match self.read_head() {
+                Ok(tree) => Some(tree),
+                Err(err) => {
+                    warn(&format!(
+                        "failed to read HEAD of Git repository {:?}: {}",
+                        self.work_dir, err
+                    ));
+                    None
+                }
+            })
+        }))
+    }
+
+    fn is_modified(&self, path: &Path, entry: &IndexEntry) -> bool {
+        if entry.stage != 0 {
+            return true; // unmerged
+        }
+
+        let meta = match path.symlink_metadata() {
+            Ok(meta) => meta,
+            Err(_) => return false,
+        };
+        let is_symlink = meta.file_type().is_symlink();
+
+        match entry.mode & 0o170000 {
+            0o160000 => return false, // submodules are not inspected
+            0o120000 if !is_symlink => return true,
+            0o100000 if !meta.is_file() => return true,
+            0o100000 if (meta.mode() & 0o100 != 0) != (entry.mode & 0o100 != 0) => return true,
+            _ => (),
+        }
+
+        if meta.size() as u32 != entry.size {
+            return true;
+        }
+        // NOTE: the file may be modified again within the same timestamp granularity
+        if (meta.mtime() as u32, meta.mtime_nsec() as u32) == entry.mtime {
+            return false;
+        }
+
+        match hash_blob(path, is_symlink, meta.size()) {
+            Ok(id) => id != entry.id,
+            Err(err) => {
+                warn(&format!("could not read {:?}: {}", path, err));
+                false
+            }
+        }
+    }
+
+    fn is_staged(&self, path: &[u8], entry: &IndexEntry) -> bool {
+        if entry.stage != 0 {
+            return true; // unmerged
+        }
+
+        match *self.head() {
+            Some(ref tree) => tree
+                .get(path)
+                .map_or(true, |&(mode, id)| mode != entry.mode || id != entry.id),
+            None => false,
+        }
+    }
+}
+
+trait StripNewline {
+    fn strip_suffix_newline(&self) -> &[u8];
+}
+
+impl StripNewline for [u8] {
+    fn strip_suffix_newline(&self) -> &[u8] {
+        let mut text = self;
+
+        while text
+            .last()
+            .map_or(false, |&byte| byte == b'\n' || byte == b'\r')
+        {
+            text = &text[..text.len() - 1];
+        }
+
+        text
+    }
+}
+
+// The ID of the file content as a blob, without applying any filter like end-of-line
+// conversion.
+fn hash_blob(path: &Path, is_symlink: bool, size: u64) -> io::Result<ObjectId> {
+    let mut sha1 = Sha1::new();
+
+    if is_symlink {
+        let target = fs::read_link(path)?;
+        let target = target.as_os_str().as_bytes();
+
+        sha1.update(format!("blob {}\0", target.len()).as_bytes());
+        sha1.update(target);
+    } else {
+        let mut file = File::open(path)?;
+        let mut buffer = vec![0; 64 * 1024];
+
+        sha1.update(format!("blob {}\0", size).as_bytes());
+        loop {
+            match file.read(&mut buffer)? {
+                0 => break,
+                count => sha1.update(&buffer[..count]),
+            }
+        }
+    }
+
+    Ok(ObjectId(sha1.digest().bytes()))
+}
+
+// Check the status of a file in the repository of which the working tree contains it.
+// Directories are tracked if any file in it is tracked, but never modified or staged.
+pub fn test_status(path: &Path, is_dir: bool, status: GitStatus) -> bool {
+    let path = match logical_path(path) {
+        Ok(path) => path,
+        Err(_) => return false,
+    };
+    let repo = match path.parent().and_then(find_repository) {
+        Some(repo) => repo,
+        None => return false,
+    };
+    let relative = match path.strip_prefix(&repo.work_dir) {
+        Ok(relative) => relative,
+        Err(_) => return false,
+    };
+
+    // not part of the working tree
+    if relative.components().next() == Some(Component::Normal(".git".as_ref())) {
+        return false;
+    }
+
+    let relative = relative.as_os_str().as_bytes();
+    let entry = repo.index.get(relative);
+
+    match status {
+        GitStatus::Tracked => entry.is_some() || (is_dir && repo.index.contains_dir(relative)),
+        GitStatus::Untracked => entry.is_none() && !(is_dir && repo.index.contains_dir(relative)),
+        GitStatus::Modified => {
+            !is_dir && entry.map_or(false, |entry| repo.is_modified(&path, entry))
+        }
+        GitStatus::Staged => {
+            !is_dir && entry.map_or(false, |entry| repo.is_staged(relative, entry))
+        }
+    }
+}
@@ -0,0 +1,748 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::super::flate2::read::ZlibDecoder;
+use super::ObjectId;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ObjectKind {
+    Commit,
+    Tree,
+    Blob,
+    Tag,
+}
+
+impl ObjectKind {
+    fn from_name(name: &[u8]) -> io::Result<ObjectKind> {
+        match name {
+            b"commit" => Ok(ObjectKind::Commit),
+            b"tree" => Ok(ObjectKind::Tree),
+            b"blob" => Ok(ObjectKind::Blob),
+            b"tag" => Ok(ObjectKind::Tag),
+            _ => Err(invalid("found unknown object type")),
+        }
+    }
+
+    fn from_code(code: u8) -> io::Result<ObjectKind> {
+        match code {
+            1 => Ok(ObjectKind::Commit),
+            2 => Ok(ObjectKind::Tree),
+            3 => Ok(ObjectKind::Blob),
+            4 => Ok(ObjectKind::Tag),
+            _ => Err(invalid("found unknown object type in pack")),
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn not_found(id: &ObjectId) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("could not find object {}", id.to_hex()),
+    )
+}
+
+// Git allows delta chains of at most 4095 objects.
+const MAX_DELTA_DEPTH: usize = 4095;
+// the total size of the resolved objects kept for resolving other deltas
+const BASE_CACHE_SIZE: usize = 16 * 1024 * 1024;
+
+// https://git-scm.com/docs/pack-format
+struct Pack {
+    path: PathBuf,  // *.pack
+    index: Vec<u8>, // *.idx of version 2
+    count: usize,
+}
+
+impl Pack {
+    fn open(index_path: &Path) -> io::Result<Pack> {
+        Pack::from_index(index_path.with_extension("pack"), fs::read(index_path)?)
+    }
+
+    fn from_index(path: PathBuf, index: Vec<u8>) -> io::Result<Pack> {
+        if index.len() < 8 + 256 * 4 || index[..8] != [0xFF, b't', b'O', b'c', 0, 0, 0, 2] {
+            return Err(invalid("found unsupported pack index"));
+        }
+
+        let mut count = 0;
+
+        // the fanout table is used for binary searches without further checks
+        for byte in 0..256 {
+            let next = read_u32(&index, 8 + byte * 4) as usize;
+
+            if next < count {
+                return Err(invalid("found corrupted pack index"));
+            }
+            count = next;
+        }
+
+        if index.len() < 8 + 256 * 4 + count * (20 + 4 + 4) {
+            return Err(invalid("pack index is truncated"));
+        }
+
+        Ok(Pack { path, index, count })
+    }
+
+    fn find(&self, id: &ObjectId) -> Option<u64> {
+        let fanout = |byte: usize| {
+            if byte == 0 {
+                0
+            } else {
+                read_u32(&self.index, 8 + (byte - 1) * 4) as usize
+            }
+        };
+        let first = id.0[0] as usize;
+        let (mut lo, mut hi) = (fanout(first), fanout(first + 1));
+        let ids = 8 + 256 * 4;
+
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            let start = ids + mid * 20;
+
+            match self.index[start..start + 20].cmp(&id.0[..]) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => {
+                    let offsets = ids + self.count * (20 + 4);
+                    let offset = read_u32(&self.index, offsets + mid * 4);
+
+                    if offset & 0x8000_0000 == 0 {
+                        return Some(u64::from(offset));
+                    }
+
+                    let large = offsets + self.count * 4 + (offset & 0x7FFF_FFFF) as usize * 8;
+
+                    return self.index.get(large..large + 8).map(|bytes| {
+                        let mut buffer = [0; 8];
+
+                        buffer.copy_from_slice(bytes);
+                        u64::from_be_bytes(buffer)
+                    });
+                }
+            }
+        }
+
+        None
+    }
+}
+
+struct Cursor<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn byte(&mut self) -> io::Result<u8> {
+        let byte = self.data.get(self.position).cloned();
+
+        self.position += 1;
+        byte.ok_or_else(|| invalid("found truncated data"))
+    }
+
+    // the little-endian variable-width integer
+    fn size(&mut self) -> io::Result<usize> {
+        let mut value = 0;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.byte()?;
+
+            if shift > 57 {
+                return Err(invalid("found invalid size"));
+            }
+            value |= ((byte & 0x7F) as usize) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+}
+
+fn read_u32(data: &[u8], start: usize) -> u32 {
+    u32::from_be_bytes([
+        data[start],
+        data[start + 1],
+        data[start + 2],
+        data[start + 3],
+    ])
+}
+
+// Where an object is stored.
+enum Location {
+    Loose(Vec<u8>),     // the compressed file
+    Packed(usize, u64), // the pack and the offset in it
+}
+
+// The base object of a deltified object in a pack.
+enum Base {
+    Offset(u64),
+    Id(ObjectId),
+}
+
+// The objects resolved from deltas, by packs and offsets. All objects are dropped at once
+// when the cache is full, which is good enough for reading trees of the same commit.
+#[derive(Default)]
+struct BaseCache {
+    objects: HashMap<(usize, u64), (ObjectKind, Vec<u8>)>,
+    size: usize,
+}
+
+// The object database of a repository, including the alternate object directories.
+pub struct ObjectStore {
+    dirs: Vec<PathBuf>,
+    packs: Mutex<Option<Arc<Vec<Pack>>>>,
+    bases: Mutex<BaseCache>,
+}
+
+impl ObjectStore {
+    pub fn new(objects_dir: &Path) -> ObjectStore {
+        let mut dirs = vec![objects_dir.to_path_buf()];
+
+        if let Ok(text) = fs::read(objects_dir.join("info/alternates")) {
+            for line in text.split(|&byte| byte == b'\n') {
+                if line.is_empty() || line.starts_with(b"#") {
+                    continue;
+                }
+                if let Ok(line) = std::str::from_utf8(line) {
+                    dirs.push(objects_dir.join(line));
+                }
+            }
+        }
+
+        ObjectStore {
+            dirs,
+            packs: Mutex::new(None),
+            bases: Mutex::new(BaseCache::default()),
+        }
+    }
+
+    pub fn read(&self, id: &ObjectId) -> io::Result<(ObjectKind, Vec<u8>)> {
+        let packs = self.packs();
+
+        match self.locate(&packs, id)? {
+            Location::Loose(data) => parse_loose(&inflate_loose(&data)?),
+            Location::Packed(pack, offset) => self.read_packed(&packs, pack, offset),
+        }
+    }
+
+    fn packs(&self) -> Arc<Vec<Pack>> {
+        Arc::clone(
+            self.packs
+                .lock()
+                .unwrap()
+                .get_or_insert_with(|| Arc::new(self.open_packs())),
+        )
+    }
+
+    fn open_packs(&self) -> Vec<Pack> {
+        let mut packs = Vec::new();
+
+        for dir in &self.dirs {
+            if let Ok(entries) = fs::read_dir(dir.join("pack")) {
+                for entry in entries.filter_map(Result::ok) {
+                    let path = entry.path();
+
+                    if path.extension().map_or(false, |ext| ext == "idx") {
+                        if let Ok(pack) = Pack::open(&path) {
+                            packs.push(pack);
+                        }
+                    }
+                }
+            }
+        }
+
+        packs
+    }
+
+    fn locate(&self, packs: &[Pack], id: &ObjectId) -> io::Result<Location> {
+        let hex = id.to_hex();
+
+        for dir in &self.dirs {
+            match fs::read(dir.join(&hex[..2]).join(&hex[2..])) {
+                Ok(data) => return Ok(Location::Loose(data)),
+                Err(ref err) if err.kind() == io::ErrorKind::NotFound => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        for (index, pack) in packs.iter().enumerate() {
+            if let Some(offset) = pack.find(id) {
+                return Ok(Location::Packed(index, offset));
+            }
+        }
+
+        Err(not_found(id))
+    }
+
+    // Follow the delta chain down to a base object, then apply the deltas in reverse order.
+    // The chain is walked in a loop, so corrupted packs with cycles cannot overflow the stack.
+    fn read_packed(
+        &self,
+        packs: &[Pack],
+        mut pack: usize,
+        mut offset: u64,
+    ) -> io::Result<(ObjectKind, Vec<u8>)> {
+        let mut deltas = Vec::new();
+        let mut file: Option<(usize, File)> = None;
+
+        let (kind, mut data) = loop {
+            if let Some(object) = self.bases.lock().unwrap().objects.get(&(pack, offset)) {
+                break object.clone();
+            }
+            if deltas.len() > MAX_DELTA_DEPTH {
+                return Err(invalid("found too long delta chain in pack"));
+            }
+
+            if file.as_ref().map_or(true, |&(opened, _)| opened != pack) {
+                file = Some((pack, File::open(&packs[pack].path)?));
+            }
+
+            let file = &mut file.as_mut().unwrap().1;
+            let (code, size, base, position) = read_entry_header(file, offset)?;
+            let data = inflate_at(file, position, size)?;
+
+            let base = match base {
+                Some(base) => base,
+                None => {
+                    let kind = ObjectKind::from_code(code)?;
+
+                    self.cache_base(pack, offset, kind, &data);
+                    break (kind, data);
+                }
+            };
+
+            deltas.push((pack, offset, data));
+
+            match base {
+                Base::Offset(base) => offset = base,
+                Base::Id(id) => match self.locate(packs, &id)? {
+                    Location::Loose(data) => break parse_loose(&inflate_loose(&data)?)?,
+                    Location::Packed(base_pack, base) => {
+                        pack = base_pack;
+                        offset = base;
+                    }
+                },
+            }
+        };
+
+        while let Some((pack, offset, delta)) = deltas.pop() {
+            data = apply_delta(&data, &delta)?;
+            self.cache_base(pack, offset, kind, &data);
+        }
+
+        Ok((kind, data))
+    }
+
+    fn cache_base(&self, pack: usize, offset: u64, kind: ObjectKind, data: &[u8]) {
+        if data.len() > BASE_CACHE_SIZE / 4 {
+            return;
+        }
+
+        let mut cache = self.bases.lock().unwrap();
+
+        if cache.size + data.len() > BASE_CACHE_SIZE {
+            cache.objects.clear();
+            cache.size = 0;
+        }
+        cache.size += data.len();
+        cache.objects.insert((pack, offset), (kind, data.to_vec()));
+    }
+
+    // Get all files in a tree recursively with their modes and IDs.
+    pub fn read_tree(
+        &self,
+        id: &ObjectId,
+        prefix: &[u8],
+        files: &mut HashMap<Vec<u8>, (u32, ObjectId)>,
+    ) -> io::Result<()> {
+        let (kind, data) = self.read(id)?;
+
+        if kind != ObjectKind::Tree {
+            return Err(invalid("found non-tree object in tree"));
+        }
+
+        let mut rest = &data[..];
+
+        while !rest.is_empty() {
+            let space = rest
+                .iter()
+                .position(|&byte| byte == b' ')
+                .ok_or_else(|| invalid("found corrupted tree object"))?;
+            let nul = rest
+                .iter()
+                .position(|&byte| byte == 0)
+                .ok_or_else(|| invalid("found corrupted tree object"))?;
+
+            if space > nul || rest.len() < nul + 21 {
+                return Err(invalid("found corrupted tree object"));
+            }
+
+            let mode = std::str::from_utf8(&rest[..space])
+                .ok()
+                .and_then(|mode| u32::from_str_radix(mode, 8).ok())
+                .ok_or_else(|| invalid("found corrupted tree object"))?;
+            let mut path = prefix.to_vec();
+
+            if !path.is_empty() {
+                path.push(b'/');
+            }
+            path.extend_from_slice(&rest[space + 1..nul]);
+
+            let entry_id = ObjectId::from_bytes(&rest[nul + 1..nul + 21]);
+
+            if mode == 0o040000 {
+                self.read_tree(&entry_id, &path, files)?;
+            } else {
+                files.insert(path, (mode, entry_id));
+            }
+
+            rest = &rest[nul + 21..];
+        }
+
+        Ok(())
+    }
+
+    // Get the tree of a commit.
+    pub fn commit_tree(&self, id: &ObjectId) -> io::Result<ObjectId> {
+        let (kind, data) = self.read(id)?;
+
+        if kind != ObjectKind::Commit || !data.starts_with(b"tree ") || data.len() < 45 {
+            return Err(invalid("found corrupted commit object"));
+        }
+
+        ObjectId::from_hex(&data[5..45]).ok_or_else(|| invalid("found corrupted commit object"))
+    }
+}
+
+fn read_some(file: &mut File, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+
+    while len < buffer.len() {
+        match file.read(&mut buffer[len..])? {
+            0 => break,
+            count => len += count,
+        }
+    }
+
+    Ok(len)
+}
+
+// Read the type, the size and the base (for deltas) of an object in a pack, and where the
+// compressed data starts.
+fn read_entry_header(file: &mut File, offset: u64) -> io::Result<(u8, usize, Option<Base>, u64)> {
+    let mut header = [0; 32];
+
+    file.seek(SeekFrom::Start(offset))?;
+
+    let len = read_some(file, &mut header)?;
+    let mut cursor = Cursor {
+        data: &header[..len],
+        position: 0,
+    };
+
+    let mut byte = cursor.byte()?;
+    let code = (byte >> 4) & 0x7;
+    let mut size = (byte & 0x0F) as usize;
+    let mut shift = 4;
+
+    while byte & 0x80 != 0 {
+        if shift > 57 {
+            return Err(invalid("found invalid object size in pack"));
+        }
+        byte = cursor.byte()?;
+        size |= ((byte & 0x7F) as usize) << shift;
+        shift += 7;
+    }
+
+    let base = match code {
+        // OFS_DELTA: the base is at a negative offset in the same pack
+        6 => {
+            byte = cursor.byte()?;
+
+            let mut distance = u64::from(byte & 0x7F);
+
+            while byte & 0x80 != 0 {
+                if distance >= 1 << 56 {
+                    return Err(invalid("found invalid delta base in pack"));
+                }
+                byte = cursor.byte()?;
+                distance = ((distance + 1) << 7) | u64::from(byte & 0x7F);
+            }
+            if distance == 0 || distance > offset {
+                return Err(invalid("found invalid delta base in pack"));
+            }
+
+            Some(Base::Offset(offset - distance))
+        }
+        // REF_DELTA: the base is referred by its ID
+        7 => {
+            let mut id = [0; 20];
+
+            for byte in id.iter_mut() {
+                *byte = cursor.byte()?;
+            }
+
+            Some(Base::Id(ObjectId(id)))
+        }
+        _ => None,
+    };
+
+    Ok((code, size, base, offset + cursor.position as u64))
+}
+
+fn inflate_loose(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut object = Vec::with_capacity(data.len() * 2);
+
+    ZlibDecoder::new(data).read_to_end(&mut object)?;
+
+    Ok(object)
+}
+
+// The compressed size is unknown, so the data is decoded until the stream is complete.
+fn inflate_at(file: &mut File, offset: u64, size: usize) -> io::Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(size.min(1024 * 1024));
+
+    file.seek(SeekFrom::Start(offset))?;
+    ZlibDecoder::new(BufReader::new(file))
+        .take(size as u64 + 1)
+        .read_to_end(&mut data)?;
+
+    if data.len() != size {
+        return Err(invalid("found object of wrong size in pack"));
+    }
+
+    Ok(data)
+}
+
+fn parse_loose(data: &[u8]) -> io::Result<(ObjectKind, Vec<u8>)> {
+    let nul = data
+        .iter()
+        .position(|&byte| byte == 0)
+        .ok_or_else(|| invalid("found corrupted loose object"))?;
+    let space = data[..nul]
+        .iter()
+        .position(|&byte| byte == b' ')
+        .ok_or_else(|| invalid("found corrupted loose object"))?;
+
+    Ok((
+        ObjectKind::from_name(&data[..space])?,
+        data[nul + 1..].to_vec(),
+    ))
+}
+
+// https://git-scm.com/docs/pack-format#_deltified_representation
+fn apply_delta(base: &[u8], delta: &[u8]) -> io::Result<Vec<u8>> {
+    let mut cursor = Cursor {
+        data: delta,
+        position: 0,
+    };
+    let base_size = cursor.size()?;
+    let result_size = cursor.size()?;
+
+    if base_size != base.len() {
+        return Err(invalid("found delta with wrong base size"));
+    }
+
+    // the sizes are not trusted for allocation
+    let mut result = Vec::with_capacity(result_size.min(base.len() + delta.len()));
+
+    while result.len() < result_size {
+        let op = cursor.byte()?;
+
+        if op & 0x80 != 0 {
+            let mut offset = 0;
+            let mut len = 0;
+
+            for i in 0..4 {
+                if op & (1 << i) != 0 {
+                    offset |= (cursor.byte()? as usize) << (i * 8);
+                }
+            }
+            for i in 0..3 {
+                if op & (0x10 << i) != 0 {
+                    len |= (cursor.byte()? as usize) << (i * 8);
+                }
+            }
+            if len == 0 {
+                len = 0x10000;
+            }
+
+            let data = base
+                .get(offset..offset + len)
+                .ok_or_else(|| invalid("found corrupted delta"))?;
+
+            result.extend_from_slice(data);
+        } else if op != 0 {
+            for _ in 0..op {
+                result.push(cursor.byte()?);
+            }
+        } else {
+            return Err(invalid("found corrupted delta"));
+        }
+    }
+
+    if result.len() != result_size {
+        return Err(invalid("found delta with wrong result size"));
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::flate2::write::ZlibEncoder;
+    use super::super::super::flate2::Compression;
+    use super::super::super::tempfile;
+    use super::*;
+    use std::io::Write;
+
+    // A pack with objects of fake IDs, which are not verified by the readers.
+    struct PackBuilder {
+        data: Vec<u8>,
+        objects: Vec<(ObjectId, u64)>,
+    }
+
+    impl PackBuilder {
+        fn new() -> PackBuilder {
+            PackBuilder {
+                data: b"PACK\x00\x00\x00\x02\x00\x00\x00\x00".to_vec(),
+                objects: Vec::new(),
+            }
+        }
+
+        fn add(&mut self, id: u8, code: u8, base: &[u8], data: &[u8]) -> u64 {
+            let offset = self.data.len() as u64;
+            let mut size = data.len() >> 4;
+            let mut byte = (code << 4) | (data.len() & 0x0F) as u8;
+
+            while size != 0 {
+                self.data.push(byte | 0x80);
+                byte = (size & 0x7F) as u8;
+                size >>= 7;
+            }
+            self.data.push(byte);
+            self.data.extend_from_slice(base);
+
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+
+            encoder.write_all(data).unwrap();
+            self.data.extend(encoder.finish().unwrap());
+            self.objects.push((ObjectId([id; 20]), offset));
+
+            offset
+        }
+
+        fn store(&mut self, dir: &Path) -> ObjectStore {
+            let mut index = b"\xFFtOc\x00\x00\x00\x02".to_vec();
+
+            self.objects.sort_by_key(|&(id, _)| id.0);
+            for byte in 0..256 {
+                let count = self
+                    .objects
+                    .iter()
+                    .filter(|&&(id, _)| id.0[0] as usize <= byte)
+                    .count();
+
+                index.extend_from_slice(&(count as u32).to_be_bytes());
+            }
+            for &(id, _) in &self.objects {
+                index.extend_from_slice(&id.0);
+            }
+            for _ in &self.objects {
+                index.extend_from_slice(&[0; 4]); // CRC32
+            }
+            for &(_, offset) in &self.objects {
+                index.extend_from_slice(&(offset as u32).to_be_bytes());
+            }
+
+            fs::create_dir_all(dir.join("pack")).unwrap();
+            fs::write(dir.join("pack/test.pack"), &self.data).unwrap();
+            fs::write(dir.join("pack/test.idx"), &index).unwrap();
+
+            ObjectStore::new(dir)
+        }
+    }
+
+    #[test]
+    fn git_delta() {
+        let base = b"hello world";
+        // base size 11, result size 17, copy "hello" from 0, insert " there", copy " world"
+        let delta = [
+            11, 17, 0x90, 5, 6, b' ', b't', b'h', b'e', b'r', b'e', 0x91, 5, 6,
+        ];
+
+        assert_eq!(apply_delta(base, &delta).unwrap(), b"hello there world");
+        assert!(apply_delta(b"hello", &delta).is_err());
+
+        // sizes of too many bytes, and a result size much larger than the delta
+        assert!(apply_delta(base, &[0xFF; 11]).is_err());
+        assert!(apply_delta(base, &[11, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 0x91, 5, 6]).is_err());
+    }
+
+    #[test]
+    fn git_pack_index() {
+        let mut index = b"\xFFtOc\x00\x00\x00\x02".to_vec();
+
+        index.resize(8 + 256 * 4, 0);
+        assert!(Pack::from_index(PathBuf::new(), index.clone()).is_ok());
+
+        // decreasing counts in the fanout table
+        index[8 + 3] = 1;
+        assert!(Pack::from_index(PathBuf::new(), index.clone()).is_err());
+
+        // missing IDs and offsets
+        for byte in 0..256 {
+            index[8 + byte * 4 + 3] = 1;
+        }
+        assert!(Pack::from_index(PathBuf::new(), index.clone()).is_err());
+        index.resize(8 + 256 * 4 + 28, 0);
+        assert!(Pack::from_index(PathBuf::new(), index).is_ok());
+    }
+
+    #[test]
+    fn git_pack_delta() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut pack = PackBuilder::new();
+        let blob = pack.add(1, 3, &[], b"hello world");
+        let ofs_delta = pack.add(
+            2,
+            6,
+            &[(pack.data.len() as u64 - blob) as u8],
+            &[
+                11, 17, 0x90, 5, 6, b' ', b't', b'h', b'e', b'r', b'e', 0x91, 5, 6,
+            ],
+        );
+
+        pack.add(3, 7, &[2; 20], &[17, 5, 0x90, 5]);
+        // a delta of itself, and a delta at a distance of zero
+        pack.add(4, 7, &[4; 20], &[5, 5, 0x90, 5]);
+        pack.add(5, 6, &[0], &[5, 5, 0x90, 5]);
+
+        let store = pack.store(dir.path());
+        let read = |id: u8| store.read(&ObjectId([id; 20]));
+
+        assert!(ofs_delta > blob);
+        assert_eq!(
+            read(1).unwrap(),
+            (ObjectKind::Blob, b"hello world".to_vec())
+        );
+        assert_eq!(
+            read(2).unwrap(),
+            (ObjectKind::Blob, b"hello there world".to_vec())
+        );
+        assert_eq!(read(3).unwrap(), (ObjectKind::Blob, b"hello".to_vec()));
+        assert_eq!(read(4).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(read(5).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(read(6).unwrap_err().kind(), io::ErrorKind::NotFound);
+
+        // the resolved bases are cached
+        assert!(store.bases.lock().unwrap().objects.len() >= 2);
+    }
+}
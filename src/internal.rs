@@ -4,9 +4,13 @@ use std::io::Write;
 use std::path::PathBuf;
 use std::process;
 
+use super::exclude::ExcludeSet;
 use super::exec::ExecTemplate;
 use super::filter::Chain as FilterChain;
+use super::fshelper::PathResolution;
 use super::lscolors::LsColors;
+use super::output::{Column, OutputFormat, PathTransform};
+use super::sort::SortKey;
 
 #[derive(Debug)]
 pub enum Error {
@@ -45,18 +49,42 @@ pub struct AppOptions {
     // Whether to match the absolute path or just the base name.
     pub match_full_path: bool,
 
-    // Whether the search results are sorted by pathname.
-    pub sort_path: bool,
+    // The keys for sorting the search results. No sorting if empty.
+    pub sort_keys: Vec<SortKey>,
+
+    // Whether to walk the directories in sorted order.
+    pub ordered: bool,
+
+    // Whether the search results are sorted in reverse order.
+    pub reverse: bool,
+
+    // The memory budget for sorting before spilling search results to temporary files.
+    pub sort_memory: Option<usize>, // bytes
 
     // Whether to include dot-files.
     pub dot_files: bool,
 
-    // Whether to respect VCS ignore files (.gitignore, .ignore, etc.).
+    // Whether to respect ignore files (.gitignore, .ignore, .ffignore, etc.).
     pub read_ignore: bool,
 
+    // Whether to respect VCS ignore files (.gitignore, .git/info/exclude, etc.).
+    pub read_vcs_ignore: bool,
+
+    // Whether to respect ignore files in the parent directories of the starting points.
+    pub read_parent_ignore: bool,
+
+    // Whether to respect the global ignore file of Git (core.excludesFile).
+    pub read_global_ignore: bool,
+
+    // The additional ignore files in global gitignore syntax.
+    pub ignore_files: Vec<PathBuf>,
+
     // Whether to multiplex stdin.
     pub multiplex: bool,
 
+    // Whether to suppress search results of the same file reached through another path.
+    pub unique: bool,
+
     // Whether to follow symbolic links.
     pub follow_symlink: bool,
 
@@ -71,18 +99,30 @@ pub struct AppOptions {
 
     // TODO: min_depth
 
+    // The maximum number of search results.
+    pub max_results: Option<usize>,
+
     // The number of threads to use.
     pub threads: usize,
 
+    // The number of bytes read from the start of a file to tell text from binary.
+    pub sniff_size: usize,
+
     // The amount of time for buffering and sorting before streaming the search results.
     pub max_buffer_time: Option<u64>, // milliseconds
 
     // The starting points for searching.
     pub includes: Vec<PathBuf>,
 
+    // The paths to test against the filter instead of walking the starting points.
+    pub listed_paths: Option<Vec<PathBuf>>,
+
     // The branches what will be pruned while searching.
     pub excludes: Vec<PathBuf>,
 
+    // The patterns of branches what will be pruned while searching.
+    pub exclude_patterns: ExcludeSet,
+
     // The filter for matching file paths.
     pub filter: FilterChain,
 
@@ -91,6 +131,24 @@ pub struct AppOptions {
 
     // The color scheme for output text.
     pub palette: Option<LsColors>,
+
+    // The format of search results when printed.
+    pub output_format: OutputFormat,
+
+    // The columns of search results in tabular formats.
+    pub columns: Vec<Column>,
+
+    // How file paths are resolved when printed or compared with the excluded paths.
+    pub path_resolution: PathResolution,
+
+    // The transformation of file paths when printed.
+    pub path_transform: PathTransform,
+
+    // Whether to print only the number of search results.
+    pub count: bool,
+
+    // Whether to print the totals of the search to stderr at exit.
+    pub stats: bool,
 }
 
 // XXX: https://github.com/rust-lang/rust/issues/41517
@@ -1,37 +1,51 @@
 extern crate atty;
 extern crate clap;
+extern crate flate2;
 extern crate globset;
 extern crate ignore;
 #[macro_use]
 extern crate lazy_static;
+extern crate log;
+extern crate memchr;
 extern crate nix;
 extern crate num_cpus;
 extern crate regex;
 extern crate same_file;
+extern crate sha1_smol;
 extern crate signal_hook;
+extern crate tempfile;
 
 mod app;
 mod counter;
+mod exclude;
 mod exec;
+mod fileinfo;
 mod filter;
 mod foss;
 mod fshelper;
+mod git;
 mod glob;
 mod internal;
 mod lscolors;
 mod output;
 mod pattern;
+mod sort;
+mod stats;
 mod walk;
 
+use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use self::exclude::ExcludeSetBuilder;
 use self::exec::ExecTemplate;
 use self::filter::{Chain as FilterChain, FileType, Filter};
-use self::fshelper::{exists, to_absolute_path};
+use self::fshelper::{exists, read_path_list, resolve_path, to_absolute_path, PathResolution};
 use self::internal::{die, int_error, int_error_os, AppOptions};
 use self::lscolors::LsColors;
+use self::output::{Column, OutputFormat, PathTransform, TreeStyle};
 use self::pattern::PatternBuilder;
+use self::sort::SortKey;
 
 fn normalize(path: impl AsRef<Path>) -> PathBuf {
     let path = path.as_ref();
@@ -46,10 +60,34 @@ fn normalize(path: impl AsRef<Path>) -> PathBuf {
     }
 }
 
+// Parse a size in bytes with an optional unit K, M or G (powers of 1024), e.g. 512M
+fn parse_size(name: &str, num_str: &str) -> usize {
+    let (digits, unit) = match num_str.as_bytes().last() {
+        Some(b'K') | Some(b'k') => (&num_str[..num_str.len() - 1], 1 << 10),
+        Some(b'M') | Some(b'm') => (&num_str[..num_str.len() - 1], 1 << 20),
+        Some(b'G') | Some(b'g') => (&num_str[..num_str.len() - 1], 1 << 30),
+        _ => (num_str, 1),
+    };
+
+    match usize::from_str_radix(digits, 10) {
+        Ok(num) => num
+            .checked_mul(unit)
+            .unwrap_or_else(|| int_error(name, num_str, "is too large")),
+        Err(err) => int_error(name, num_str, &err),
+    }
+}
+
 fn main() {
     let args = app::build().get_matches();
 
     let absolute = args.is_present("absolute-path");
+    let resolution = if args.is_present("real-path") {
+        PathResolution::Real
+    } else if args.is_present("logical-path") {
+        PathResolution::Logical
+    } else {
+        PathResolution::Unchanged
+    };
 
     let current_dir = PathBuf::from(".");
     let mut root_dirs = Vec::with_capacity(1);
@@ -70,25 +108,100 @@ fn main() {
             } else {
                 die(&format!("{:?} is not a file or directory", dir.as_os_str()));
             }
+        } else if resolution != PathResolution::Unchanged {
+            *dir = resolve_path(dir, resolution).into_owned();
         } else if absolute {
             *dir = to_absolute_path(dir).unwrap_or_else(|err| die(&err));
         }
     });
 
+    let read_paths = |file: &OsStr| {
+        read_path_list(file)
+            .unwrap_or_else(|err| die(&format!("failed to read paths from {:?}: {}", file, err)))
+    };
+
+    if args.value_of_os("files-from") == Some(OsStr::new("-")) {
+        if args.value_of_os("exclude-from") == Some(OsStr::new("-")) {
+            die("forbidden to read both --files-from and --exclude-from from stdin");
+        }
+        if args.is_present("multiplex") {
+            die("forbidden to read --files-from from stdin and use --multiplex at the same time");
+        }
+    }
+
+    let listed_paths = args.value_of_os("files-from").map(|file| {
+        if args
+            .value_of_os("DIRECTORY")
+            .map_or(false, |dir| normalize(dir) != current_dir)
+        {
+            die("forbidden to use --files-from with a starting point other than \".\"");
+        }
+
+        let mut paths: Vec<PathBuf> = read_paths(file).into_iter().map(normalize).collect();
+
+        if resolution != PathResolution::Unchanged {
+            for path in paths.iter_mut() {
+                *path = resolve_path(path, resolution).into_owned();
+            }
+        } else if absolute {
+            for path in paths.iter_mut() {
+                *path = to_absolute_path(path).unwrap_or_else(|err| die(&err));
+            }
+        }
+        paths
+    });
+
     let mut pruned_dirs = Vec::new();
 
     args.values_of_os("exclude").map(|values| {
         pruned_dirs.append(&mut values.map(normalize).collect());
     });
+    args.values_of_os("exclude-from").map(|values| {
+        for value in values {
+            pruned_dirs.append(&mut read_paths(value).into_iter().map(normalize).collect());
+        }
+    });
     pruned_dirs.sort_unstable();
     pruned_dirs.dedup();
 
-    if absolute {
+    if resolution != PathResolution::Unchanged {
+        for dir in pruned_dirs.iter_mut() {
+            *dir = resolve_path(dir, resolution).into_owned();
+        }
+    } else if absolute {
         for dir in pruned_dirs.iter_mut() {
             *dir = to_absolute_path(dir).unwrap_or_else(|err| die(&err));
         }
     }
 
+    let mut exclude_patterns = ExcludeSetBuilder::new();
+
+    exclude_patterns.unicode(args.is_present("unicode"));
+    args.values_of_os("exclude-glob").map(|values| {
+        for value in values {
+            exclude_patterns.add_glob(value).unwrap_or_else(|err| {
+                die(&format!(
+                    "failed to build exclude pattern {:?}:\n{}",
+                    value, err
+                ))
+            });
+        }
+    });
+    args.values_of_os("exclude-regex").map(|values| {
+        for value in values {
+            exclude_patterns.add_regex(value).unwrap_or_else(|err| {
+                die(&format!(
+                    "failed to build exclude pattern {:?}:\n{}",
+                    value, err
+                ))
+            });
+        }
+    });
+
+    let exclude_patterns = exclude_patterns
+        .build()
+        .unwrap_or_else(|err| die(&format!("failed to build exclude patterns:\n{}", err)));
+
     let max_depth = args
         .value_of("max-depth")
         .map(|num_str| match usize::from_str_radix(num_str, 10) {
@@ -101,6 +214,40 @@ fn main() {
             })
         });
 
+    let max_results = if args.is_present("one") {
+        Some(1)
+    } else {
+        args.value_of("max-results")
+            .map(|num_str| match usize::from_str_radix(num_str, 10) {
+                Ok(num) => num,
+                Err(err) => int_error("max-results", num_str, &err),
+            })
+            .or_else(|| {
+                args.value_of_os("max-results").map(|num_str| {
+                    int_error_os("max-results", &num_str, "is not an integer");
+                })
+            })
+    };
+
+    let sort_memory = args
+        .value_of("sort-memory")
+        .map(|num_str| parse_size("sort-memory", num_str))
+        .or_else(|| {
+            args.value_of_os("sort-memory").map(|num_str| {
+                int_error_os("sort-memory", &num_str, "is not a valid size");
+            })
+        });
+
+    let sniff_size = args
+        .value_of("sniff-size")
+        .map(|num_str| parse_size("sniff-size", num_str))
+        .or_else(|| {
+            args.value_of_os("sniff-size").map(|num_str| {
+                int_error_os("sniff-size", &num_str, "is not a valid size");
+            })
+        })
+        .unwrap_or(8 << 10);
+
     let max_buffer_time = args
         .value_of("max-buffer-time")
         .map(|num_str| match u64::from_str_radix(num_str, 10) {
@@ -145,6 +292,57 @@ fn main() {
         None
     };
 
+    let output_format = if args.is_present("tree") {
+        match args.value_of("tree") {
+            Some("ascii") => OutputFormat::Tree(TreeStyle::Ascii),
+            _ => OutputFormat::Tree(TreeStyle::Unicode),
+        }
+    } else if args.is_present("long") {
+        OutputFormat::Long
+    } else if args.is_present("json") {
+        OutputFormat::Json
+    } else {
+        OutputFormat::from_str(args.value_of("output-format").unwrap_or("plain"))
+    };
+
+    let columns = match args.values_of_os("columns") {
+        Some(values) => {
+            if output_format != OutputFormat::Csv && output_format != OutputFormat::Tsv {
+                die("--columns is only available for the csv and tsv output formats");
+            }
+            values
+                .map(|value| Column::from_str(value).unwrap_or_else(|err| die(&err)))
+                .collect()
+        }
+        None => vec![Column::Path],
+    };
+
+    let sort_keys = match args.values_of_os("sort") {
+        Some(values) => values
+            .map(|value| SortKey::from_str(value).unwrap_or_else(|err| die(&err)))
+            .collect(),
+        None if args.is_present("sort-path") || args.is_present("reverse") => vec![SortKey::Path],
+        None => Vec::new(),
+    };
+
+    let path_transform = if args.is_present("strip-prefix") {
+        PathTransform::StripPrefix
+    } else if args.is_present("base-name") {
+        PathTransform::BaseName
+    } else if let Some(base) = args.value_of_os("relative-to") {
+        let base = normalize(base);
+
+        if !exists(&base) {
+            die(&format!(
+                "{:?} is not a file or directory",
+                base.as_os_str()
+            ));
+        }
+        PathTransform::RelativeTo(to_absolute_path(&base).unwrap_or_else(|err| die(&err)))
+    } else {
+        PathTransform::Unchanged
+    };
+
     let command = args.values_of_os("exec").map(|cmd_args| {
         if args.occurrences_of("PATTERN") > 1 {
             die("forbidden to use filter chain and --exec at the same time");
@@ -153,26 +351,51 @@ fn main() {
         ExecTemplate::new(&cmd_args.collect())
     });
 
+    let read_ignore = !args.is_present("no-ignore");
+    let ignore_files = args
+        .values_of_os("ignore-file")
+        .map_or(Vec::new(), |values| values.map(PathBuf::from).collect());
+
     let mut config = AppOptions {
         verbose: args.is_present("verbose"),
         unicode: args.is_present("unicode"),
         use_regex: args.is_present("use-regex"),
         case_insensitive: args.is_present("ignore-case"),
         match_full_path: args.is_present("full-path"),
-        sort_path: args.is_present("sort-path"),
+        sort_keys: sort_keys,
+        reverse: args.is_present("reverse"),
+        ordered: args.is_present("ordered"),
+        sort_memory: sort_memory,
         dot_files: args.is_present("dot-files"),
-        read_ignore: !args.is_present("no-ignore"),
+        read_ignore: read_ignore,
+        read_vcs_ignore: read_ignore && !args.is_present("no-ignore-vcs"),
+        read_parent_ignore: read_ignore && !args.is_present("no-ignore-parent"),
+        read_global_ignore: read_ignore
+            && !args.is_present("no-ignore-vcs")
+            && !args.is_present("no-global-ignore"),
+        ignore_files: ignore_files,
         multiplex: args.is_present("multiplex"),
         follow_symlink: args.is_present("follow-symlink"),
+        unique: args.is_present("unique"),
         same_file_system: args.is_present("same-file-system"),
         null_terminator: args.is_present("null-terminator"),
         includes: root_dirs,
+        listed_paths: listed_paths,
         excludes: pruned_dirs,
+        exclude_patterns: exclude_patterns,
         filter: FilterChain::default(),
         command: command,
         palette: palette,
+        output_format: output_format,
+        columns: columns,
+        path_resolution: resolution,
+        path_transform: path_transform,
+        count: args.is_present("count"),
+        stats: args.is_present("stats"),
+        sniff_size: sniff_size,
         max_buffer_time: max_buffer_time,
         max_depth: max_depth,
+        max_results: max_results,
         threads: num_thread,
     };
 
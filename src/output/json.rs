@@ -0,0 +1,192 @@
+use std::ffi::OsStr;
+use std::fmt::Write as FmtWrite;
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+
+use super::super::fileinfo::Timestamp;
+use super::super::fshelper::{group_name, user_name};
+use super::super::internal::AppOptions;
+use super::super::walk::starting_point;
+use super::{transform_path, Entry};
+
+const BASE64_TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Format the search result as a JSON object without any line terminator.
+//
+// Strings that are not valid UTF-8 are lossily converted, and then the original bytes are
+// provided in an extra field "<name>_base64", e.g. {"path": "./�", "path_base64": "Li//"}
+pub fn format_entry(entry: &Entry, config: &AppOptions) -> Vec<u8> {
+    let mut json = String::from("{");
+
+    write_os_str(
+        &mut json,
+        "path",
+        transform_path(&entry.path, config).as_os_str(),
+    );
+
+    if let Some(ref info) = entry.info {
+        let _ = write!(json, r#","type":"{}""#, info.kind());
+        let _ = write!(json, r#","size":{}"#, info.size);
+        let _ = write!(json, r#","mode":{}"#, info.permissions());
+        let _ = write!(json, r#","uid":{}"#, info.uid);
+        match user_name(info.uid) {
+            Some(name) => write_os_str(&mut json, ",user", &name),
+            None => json.push_str(r#","user":null"#),
+        }
+        let _ = write!(json, r#","gid":{}"#, info.gid);
+        match group_name(info.gid) {
+            Some(name) => write_os_str(&mut json, ",group", &name),
+            None => json.push_str(r#","group":null"#),
+        }
+        write_timestamp(&mut json, "atime", &info.atime);
+        write_timestamp(&mut json, "mtime", &info.mtime);
+        write_timestamp(&mut json, "ctime", &info.ctime);
+    } else {
+        json.push_str(r#","type":null,"size":null,"mode":null"#);
+        json.push_str(r#","uid":null,"user":null,"gid":null,"group":null"#);
+        json.push_str(r#","atime":null,"mtime":null,"ctime":null"#);
+    }
+
+    let _ = write!(json, r#","depth":{}"#, entry.depth);
+
+    let is_symlink = entry.info.as_ref().map_or(false, |info| info.is_symlink());
+    let target = if is_symlink {
+        fs::read_link(&entry.path).ok()
+    } else {
+        None
+    };
+    match target {
+        Some(target) => write_os_str(&mut json, ",target", target.as_os_str()),
+        None => json.push_str(r#","target":null"#),
+    }
+
+    match starting_point(&config.includes, &entry.path, entry.depth) {
+        Some(root) => write_os_str(&mut json, ",root", root.as_os_str()),
+        None => json.push_str(r#","root":null"#),
+    }
+
+    json.push('}');
+    json.into_bytes()
+}
+
+// Write a key-value pair. The key may be prefixed with a comma.
+fn write_os_str(json: &mut String, key: &str, value: &OsStr) {
+    let (comma, key) = if key.starts_with(',') {
+        (",", &key[1..])
+    } else {
+        ("", key)
+    };
+    let bytes = value.as_bytes();
+    let lossy = String::from_utf8_lossy(bytes);
+
+    json.push_str(comma);
+    write_str(json, key);
+    json.push(':');
+    write_str(json, &lossy);
+
+    if lossy.as_bytes() != bytes {
+        json.push(',');
+        write_str(json, &format!("{}_base64", key));
+        json.push(':');
+        json.push('"');
+        json.push_str(&encode_base64(bytes));
+        json.push('"');
+    }
+}
+
+// Timestamps are seconds since the Unix epoch with nanosecond precision.
+fn write_timestamp(json: &mut String, key: &str, time: &Timestamp) {
+    let nanos = time.secs as i128 * 1_000_000_000 + time.nsecs as i128;
+    let sign = if nanos < 0 { "-" } else { "" };
+    let nanos = nanos.abs();
+
+    let _ = write!(
+        json,
+        r#","{}":{}{}.{:09}"#,
+        key,
+        sign,
+        nanos / 1_000_000_000,
+        nanos % 1_000_000_000
+    );
+}
+
+fn write_str(json: &mut String, value: &str) {
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            '\u{08}' => json.push_str("\\b"),
+            '\u{0C}' => json.push_str("\\f"),
+            c if c < '\u{20}' || c == '\u{7F}' => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity((bytes.len() + 2) / 3 * 4);
+
+    for chunk in bytes.chunks(3) {
+        let b0 = chunk[0] as usize;
+        let b1 = chunk.get(1).cloned().unwrap_or(0) as usize;
+        let b2 = chunk.get(2).cloned().unwrap_or(0) as usize;
+
+        encoded.push(BASE64_TABLE[b0 >> 2] as char);
+        encoded.push(BASE64_TABLE[((b0 & 0x03) << 4) | (b1 >> 4)] as char);
+        if chunk.len() > 1 {
+            encoded.push(BASE64_TABLE[((b1 & 0x0F) << 2) | (b2 >> 6)] as char);
+        } else {
+            encoded.push('=');
+        }
+        if chunk.len() > 2 {
+            encoded.push(BASE64_TABLE[b2 & 0x3F] as char);
+        } else {
+            encoded.push('=');
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_escape() {
+        let mut json = String::new();
+        write_str(&mut json, "a\"b\\c\nd\u{1}");
+        assert_eq!(json, r#""a\"b\\c\nd\u0001""#);
+    }
+
+    #[test]
+    fn json_base64() {
+        assert_eq!(encode_base64(b""), "");
+        assert_eq!(encode_base64(b"f"), "Zg==");
+        assert_eq!(encode_base64(b"fo"), "Zm8=");
+        assert_eq!(encode_base64(b"foo"), "Zm9v");
+        assert_eq!(encode_base64(b"./\xff"), "Li//");
+    }
+
+    #[test]
+    fn json_timestamp() {
+        let mut json = String::new();
+        write_timestamp(&mut json, "t", &Timestamp { secs: 1, nsecs: 5 });
+        write_timestamp(
+            &mut json,
+            "t",
+            &Timestamp {
+                secs: -1,
+                nsecs: 500_000_000,
+            },
+        );
+        assert_eq!(json, r#","t":1.000000005,"t":-0.500000000"#);
+    }
+}
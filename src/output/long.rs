@@ -0,0 +1,180 @@
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::super::fileinfo::{FileInfo, Timestamp};
+use super::super::fshelper::{group_name, user_name};
+use super::super::internal::AppOptions;
+use super::super::lscolors;
+use super::{broken_down_time, format_path, Entry};
+
+// Half a year like ls(1), in seconds.
+const RECENT: i64 = 31556952 / 2;
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+// Format the search result like `find -ls` without any line terminator:
+//
+//     inode blocks mode links user group size mtime path [-> target]
+//
+// Blocks are counted in 1024-byte units. Names of unknown users and groups are shown as IDs.
+pub fn format_entry(entry: &Entry, config: &AppOptions) -> Vec<u8> {
+    let mut buffer = Vec::new();
+
+    if let Some(ref info) = entry.info {
+        let user = user_name(info.uid)
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| info.uid.to_string());
+        let group = group_name(info.gid)
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| info.gid.to_string());
+
+        buffer.extend_from_slice(
+            format!(
+                "{:>6} {:>4} {} {:>3} {:<8} {:<8} {:>8} {} ",
+                info.ino,
+                (info.blocks + 1) / 2, // 512-byte blocks rounded up
+                mode_string(info),
+                info.nlink,
+                user,
+                group,
+                info.size,
+                format_time(&info.mtime)
+            )
+            .as_bytes(),
+        );
+    } else {
+        buffer.extend_from_slice(
+            format!(
+                "{:>6} {:>4} {} {:>3} {:<8} {:<8} {:>8} {:>12} ",
+                "?", "?", "??????????", "?", "?", "?", "?", "?"
+            )
+            .as_bytes(),
+        );
+    }
+
+    buffer.append(&mut format_path(&entry.path, config));
+
+    if entry.info.as_ref().map_or(false, |info| info.is_symlink()) {
+        if let Ok(target) = fs::read_link(&entry.path) {
+            buffer.extend_from_slice(b" -> ");
+
+            match config.palette {
+                Some(ref palette) => {
+                    let style = entry
+                        .path
+                        .parent()
+                        .and_then(|parent| palette.style_for_path(parent.join(&target)));
+
+                    style
+                        .map(lscolors::Style::to_ansi_term_style)
+                        .unwrap_or_default()
+                        .paint(target.as_os_str().as_bytes())
+                        .write_to(&mut buffer)
+                        .expect("write to buffer");
+                }
+                None => buffer.extend_from_slice(target.as_os_str().as_bytes()),
+            }
+        }
+    }
+
+    buffer
+}
+
+// e.g. drwxr-xr-x, -rwsr-sr-t
+fn mode_string(info: &FileInfo) -> String {
+    let mode = info.permissions();
+    let kind = match info.kind() {
+        "directory" => 'd',
+        "symlink" => 'l',
+        "block_device" => 'b',
+        "char_device" => 'c',
+        "fifo" => 'p',
+        "socket" => 's',
+        _ => '-',
+    };
+    let bit = |mask: u32, c: char| if mode & mask != 0 { c } else { '-' };
+    // setuid, setgid or sticky bit along with an execute bit
+    let exe = |mask: u32, extra: u32, on: char, off: char| match (mode & mask, mode & extra) {
+        (0, 0) => '-',
+        (0, _) => off,
+        (_, 0) => 'x',
+        (_, _) => on,
+    };
+
+    [
+        kind,
+        bit(0o400, 'r'),
+        bit(0o200, 'w'),
+        exe(0o100, 0o4000, 's', 'S'),
+        bit(0o040, 'r'),
+        bit(0o020, 'w'),
+        exe(0o010, 0o2000, 's', 'S'),
+        bit(0o004, 'r'),
+        bit(0o002, 'w'),
+        exe(0o001, 0o1000, 't', 'T'),
+    ]
+    .iter()
+    .collect()
+}
+
+// Show the year instead of the time for timestamps not within the recent half year, e.g.
+// "Oct 18 12:34" or "Oct 18  2018", in the local timezone.
+fn format_time(time: &Timestamp) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0);
+    let recent = time.secs <= now && now - time.secs < RECENT;
+
+    match broken_down_time(time, false) {
+        Some(ref tm) if recent => format!(
+            "{} {:>2} {:02}:{:02}",
+            MONTHS[tm.tm_mon as usize % 12],
+            tm.tm_mday,
+            tm.tm_hour,
+            tm.tm_min
+        ),
+        Some(ref tm) => format!(
+            "{} {:>2}  {}",
+            MONTHS[tm.tm_mon as usize % 12],
+            tm.tm_mday,
+            tm.tm_year as i64 + 1900
+        ),
+        None => format!("{:>12}", time.secs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(mode: u32) -> FileInfo {
+        let time = Timestamp { secs: 0, nsecs: 0 };
+
+        FileInfo {
+            ino: 0,
+            mode,
+            nlink: 1,
+            uid: 0,
+            gid: 0,
+            size: 0,
+            blocks: 0,
+            atime: time,
+            mtime: time,
+            ctime: time,
+        }
+    }
+
+    #[test]
+    fn long_mode_string() {
+        assert_eq!(mode_string(&info(0o040755)), "drwxr-xr-x");
+        assert_eq!(mode_string(&info(0o100644)), "-rw-r--r--");
+        assert_eq!(mode_string(&info(0o120777)), "lrwxrwxrwx");
+        assert_eq!(mode_string(&info(0o106755)), "-rwsr-sr-x");
+        assert_eq!(mode_string(&info(0o041777)), "drwxrwxrwt");
+        assert_eq!(mode_string(&info(0o107644)), "-rwSr-Sr-T");
+    }
+}
@@ -0,0 +1,317 @@
+mod json;
+mod long;
+mod table;
+mod tree;
+
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::io::{self, Write};
+use std::mem;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Component, Path, PathBuf, MAIN_SEPARATOR};
+use std::process::exit;
+
+use super::nix::libc;
+use super::nix::sys::signal::Signal::SIGPIPE;
+
+use super::fileinfo::{FileInfo, Timestamp};
+use super::filter::Action;
+use super::fshelper::{resolve_path, to_absolute_path};
+use super::internal::{die, warn, AppOptions};
+use super::lscolors;
+
+pub use self::table::Column;
+pub use self::tree::{print_tree, TreeStyle};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathTransform {
+    Unchanged,
+    StripPrefix,         // omit the leading "./"
+    RelativeTo(PathBuf), // relative to an absolute path
+    BaseName,            // only the file name
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Plain, // one path per line
+    Json,  // one JSON object per line
+    Csv,   // comma-separated values with a header row
+    Tsv,   // tab-separated values with a header row
+    Long,  // like the output of `ls -dils`
+    Tree(TreeStyle),
+}
+
+impl OutputFormat {
+    pub fn from_str(name: &str) -> OutputFormat {
+        match name {
+            "json" => OutputFormat::Json,
+            "csv" => OutputFormat::Csv,
+            "tsv" => OutputFormat::Tsv,
+            "long" => OutputFormat::Long,
+            _ => OutputFormat::Plain,
+        }
+    }
+}
+
+// Whether the metadata should be collected for each search result.
+pub fn needs_metadata(config: &AppOptions) -> bool {
+    match config.output_format {
+        OutputFormat::Plain | OutputFormat::Tree(_) => false,
+        OutputFormat::Json | OutputFormat::Long => true,
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            config.columns.iter().any(|column| column.needs_metadata())
+        }
+    }
+}
+
+pub struct Entry {
+    pub path: PathBuf,
+    pub depth: usize,
+    pub info: Option<FileInfo>,
+    pub actions: Vec<Action>,
+}
+
+impl Entry {
+    // Whether the search result is printed by any action.
+    pub fn prints(&self) -> bool {
+        self.actions.iter().any(|action| match action {
+            Action::Print | Action::Print0 | Action::Ls => true,
+            _ => false,
+        })
+    }
+}
+
+pub fn print_header(config: &AppOptions) {
+    let result = match config.output_format {
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            let mut buffer = table::format_header(config);
+
+            add_path_terminator(&mut buffer, config.null_terminator);
+            io::stdout().write_all(buffer.as_slice())
+        }
+        _ => Ok(()),
+    };
+
+    check_result(result);
+}
+
+pub fn print_entry(entry: Entry, config: &AppOptions) {
+    let buffer = match config.output_format {
+        OutputFormat::Json => json::format_entry(&entry, config),
+        OutputFormat::Csv | OutputFormat::Tsv => table::format_entry(&entry, config),
+        OutputFormat::Long => long::format_entry(&entry, config),
+        OutputFormat::Plain | OutputFormat::Tree(_) => format_path(&entry.path, config),
+    };
+
+    check_result(execute_actions(entry, buffer, config));
+}
+
+pub fn check_result(result: io::Result<()>) {
+    if let Err(err) = result {
+        if err.kind() == io::ErrorKind::BrokenPipe {
+            // silently exit
+            exit(0x80 + SIGPIPE as i32);
+        } else {
+            die(&format!("failed to print search result: {}", err));
+        }
+    }
+}
+
+// Transform the path for output without changing the path used for matching.
+pub fn transform_path<'a>(path: &'a Path, config: &AppOptions) -> Cow<'a, Path> {
+    match resolve_path(path, config.path_resolution) {
+        Cow::Borrowed(path) => transform_resolved_path(path, config),
+        Cow::Owned(path) => Cow::Owned(transform_resolved_path(&path, config).into_owned()),
+    }
+}
+
+fn transform_resolved_path<'a>(path: &'a Path, config: &AppOptions) -> Cow<'a, Path> {
+    match config.path_transform {
+        PathTransform::Unchanged => Cow::Borrowed(path),
+        PathTransform::StripPrefix => match path.strip_prefix(".") {
+            Ok(rest) if !rest.as_os_str().is_empty() => Cow::Borrowed(rest),
+            _ => Cow::Borrowed(path),
+        },
+        PathTransform::BaseName => Cow::Borrowed(path.file_name().map_or(path, Path::new)),
+        PathTransform::RelativeTo(ref base) => match to_absolute_path(path) {
+            Ok(absolute) => Cow::Owned(relative_path(&absolute, base)),
+            Err(_) => Cow::Borrowed(path),
+        },
+    }
+}
+
+// Get the relative path from one absolute path to another without resolving symlinks.
+fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let path = path
+        .components()
+        .filter(|compo| *compo != Component::CurDir)
+        .collect::<Vec<_>>();
+    let base = base
+        .components()
+        .filter(|compo| *compo != Component::CurDir)
+        .collect::<Vec<_>>();
+    let common = path
+        .iter()
+        .zip(base.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative = PathBuf::new();
+
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for compo in &path[common..] {
+        relative.push(compo.as_os_str());
+    }
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+
+    relative
+}
+
+// Transform the path for output, and colorize each component of it if a palette is given.
+fn format_path(path: &Path, config: &AppOptions) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    let shown = transform_path(path, config);
+
+    if let Some(ref palette) = config.palette {
+        // traverse the original path and colorize each component
+        let styles = palette.style_for_path_components(path).collect::<Vec<_>>();
+        let count = shown.components().count();
+
+        for (i, compo) in shown.components().enumerate() {
+            // the shown path shares the trailing components with the original path,
+            // except for the leading ".." components of a relative path, and the last one
+            // always refers to the same file
+            let style = (styles.len() + i)
+                .checked_sub(count)
+                .map(|j| &styles[j])
+                .filter(|(original, _)| Path::new(original) == Path::new(compo.as_os_str()))
+                .or_else(|| styles.last().filter(|_| i + 1 == count))
+                .and_then(|&(_, style)| style);
+            let mut name = compo.as_os_str().to_os_string();
+
+            if i + 1 < count && compo != Component::RootDir {
+                name.push(MAIN_SEPARATOR.to_string());
+            }
+            style
+                .map(lscolors::Style::to_ansi_term_style)
+                .unwrap_or_default()
+                .paint(name.as_bytes())
+                .write_to(&mut buffer)
+                .expect("write to buffer");
+        }
+    } else {
+        buffer.extend_from_slice(shown.as_os_str().as_bytes());
+    }
+
+    buffer
+}
+
+fn execute_actions(entry: Entry, mut buffer: Vec<u8>, config: &AppOptions) -> io::Result<()> {
+    let mut print_checked = false;
+    let mut print0_checked = false;
+
+    for action in &entry.actions {
+        match action {
+            Action::Print => {
+                if !print_checked {
+                    check_path(&buffer, false);
+                    print_checked = true;
+                }
+                add_path_terminator(&mut buffer, false);
+            }
+            Action::Print0 => {
+                if !print0_checked {
+                    check_path(&buffer, true);
+                    print0_checked = true;
+                }
+                add_path_terminator(&mut buffer, true);
+            }
+            Action::Ls => {
+                let mut line = if config.output_format == OutputFormat::Long {
+                    buffer.clone()
+                } else {
+                    long::format_entry(&entry, config)
+                };
+
+                check_path(&line, false);
+                add_path_terminator(&mut line, false);
+                io::stdout().write_all(line.as_slice())?;
+                continue;
+            }
+            _ => continue,
+        }
+        io::stdout().write_all(buffer.as_slice())?;
+        buffer.pop(); // drop terminator
+    }
+
+    Ok(())
+}
+
+pub fn add_path_terminator(buffer: &mut Vec<u8>, null_terminated: bool) {
+    if null_terminated {
+        buffer.push(b'\0');
+    } else {
+        buffer.push(b'\n');
+    }
+}
+
+fn check_path(path: &Vec<u8>, null_terminated: bool) {
+    // int execve(const char *path, char *const argv[], char *const envp[]);
+    if path.contains(&b'\0') {
+        die(&format!(
+            "{:?} contains the nul character {:?}",
+            OsStr::from_bytes(path),
+            OsStr::new("\0")
+        ));
+    }
+    // IDEA: option for turning off warnings?
+    // reminder for poor scripts
+    if !null_terminated && path.contains(&b'\n') {
+        warn(&format!(
+            "{:?} contains the line terminator {:?}",
+            OsStr::from_bytes(path),
+            OsStr::new("\n")
+        ));
+    }
+}
+
+// Convert a timestamp into the broken-down time in UTC or the local timezone.
+fn broken_down_time(time: &Timestamp, utc: bool) -> Option<libc::tm> {
+    let secs = time.secs as libc::time_t;
+    let mut tm: libc::tm = unsafe { mem::zeroed() };
+
+    let result = unsafe {
+        if utc {
+            libc::gmtime_r(&secs, &mut tm)
+        } else {
+            libc::localtime_r(&secs, &mut tm)
+        }
+    };
+
+    if result.is_null() {
+        None
+    } else {
+        Some(tm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relative(path: &str, base: &str) -> PathBuf {
+        relative_path(Path::new(path), Path::new(base))
+    }
+
+    #[test]
+    fn output_relative_path() {
+        assert_eq!(relative("/a/b/c", "/a"), PathBuf::from("b/c"));
+        assert_eq!(relative("/a/b/c", "/a/d/e"), PathBuf::from("../../b/c"));
+        assert_eq!(relative("/a", "/a/b"), PathBuf::from(".."));
+        assert_eq!(relative("/a/./b", "/a/b/"), PathBuf::from("."));
+        assert_eq!(relative("/", "/a"), PathBuf::from(".."));
+    }
+}
@@ -0,0 +1,248 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+
+use super::super::fileinfo::Timestamp;
+use super::super::fshelper::{group_name, user_name};
+use super::super::internal::{AppOptions, Error};
+use super::super::walk::starting_point;
+use super::{broken_down_time, transform_path, Entry, OutputFormat};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Column {
+    Path,
+    Name,
+    Type,
+    Size,
+    Mode,
+    Uid,
+    User,
+    Gid,
+    Group,
+    Atime,
+    Mtime,
+    Ctime,
+    Depth,
+    Target,
+    Root,
+}
+
+impl Column {
+    pub fn from_str(name: &OsStr) -> Result<Column, Error> {
+        match name.as_bytes() {
+            b"path" => Ok(Column::Path),
+            b"name" => Ok(Column::Name),
+            b"type" => Ok(Column::Type),
+            b"size" => Ok(Column::Size),
+            b"mode" => Ok(Column::Mode),
+            b"uid" => Ok(Column::Uid),
+            b"user" => Ok(Column::User),
+            b"gid" => Ok(Column::Gid),
+            b"group" => Ok(Column::Group),
+            b"atime" => Ok(Column::Atime),
+            b"mtime" => Ok(Column::Mtime),
+            b"ctime" => Ok(Column::Ctime),
+            b"depth" => Ok(Column::Depth),
+            b"target" => Ok(Column::Target),
+            b"root" => Ok(Column::Root),
+            _ => Err(Error::from_str(&format!(
+                "found unrecognized column {:?}",
+                name
+            ))),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Column::Path => "path",
+            Column::Name => "name",
+            Column::Type => "type",
+            Column::Size => "size",
+            Column::Mode => "mode",
+            Column::Uid => "uid",
+            Column::User => "user",
+            Column::Gid => "gid",
+            Column::Group => "group",
+            Column::Atime => "atime",
+            Column::Mtime => "mtime",
+            Column::Ctime => "ctime",
+            Column::Depth => "depth",
+            Column::Target => "target",
+            Column::Root => "root",
+        }
+    }
+
+    pub fn needs_metadata(&self) -> bool {
+        match self {
+            Column::Path | Column::Name | Column::Depth | Column::Root => false,
+            _ => true,
+        }
+    }
+}
+
+// Format the header row without any line terminator.
+pub fn format_header(config: &AppOptions) -> Vec<u8> {
+    let mut buffer = Vec::new();
+
+    for (i, column) in config.columns.iter().enumerate() {
+        if i > 0 {
+            buffer.push(separator(config.output_format));
+        }
+        buffer.extend_from_slice(column.name().as_bytes());
+    }
+
+    buffer
+}
+
+// Format the search result as a row without any line terminator.
+pub fn format_entry(entry: &Entry, config: &AppOptions) -> Vec<u8> {
+    let mut buffer = Vec::new();
+
+    for (i, column) in config.columns.iter().enumerate() {
+        if i > 0 {
+            buffer.push(separator(config.output_format));
+        }
+
+        let field = format_field(entry, config, *column);
+
+        match config.output_format {
+            OutputFormat::Tsv => write_tsv_field(&mut buffer, &field),
+            _ => write_csv_field(&mut buffer, &field),
+        }
+    }
+
+    buffer
+}
+
+fn separator(format: OutputFormat) -> u8 {
+    match format {
+        OutputFormat::Tsv => b'\t',
+        _ => b',',
+    }
+}
+
+// Unavailable values are left empty.
+fn format_field(entry: &Entry, config: &AppOptions, column: Column) -> Vec<u8> {
+    let info = entry.info.as_ref();
+    let os_str = |value: &OsStr| value.as_bytes().to_vec();
+
+    match column {
+        Column::Path => os_str(transform_path(&entry.path, config).as_os_str()),
+        Column::Name => entry.path.file_name().map(os_str).unwrap_or_default(),
+        Column::Depth => entry.depth.to_string().into_bytes(),
+        Column::Root => starting_point(&config.includes, &entry.path, entry.depth)
+            .map(|root| os_str(root.as_os_str()))
+            .unwrap_or_default(),
+        Column::Target => match info {
+            Some(info) if info.is_symlink() => fs::read_link(&entry.path)
+                .map(|target| os_str(target.as_os_str()))
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        },
+        _ => match info {
+            Some(info) => match column {
+                Column::Type => info.kind().as_bytes().to_vec(),
+                Column::Size => info.size.to_string().into_bytes(),
+                Column::Mode => format!("{:04o}", info.permissions()).into_bytes(),
+                Column::Uid => info.uid.to_string().into_bytes(),
+                Column::User => user_name(info.uid)
+                    .map(|name| os_str(&name))
+                    .unwrap_or_default(),
+                Column::Gid => info.gid.to_string().into_bytes(),
+                Column::Group => group_name(info.gid)
+                    .map(|name| os_str(&name))
+                    .unwrap_or_default(),
+                Column::Atime => format_time(&info.atime).into_bytes(),
+                Column::Mtime => format_time(&info.mtime).into_bytes(),
+                Column::Ctime => format_time(&info.ctime).into_bytes(),
+                _ => unreachable!(),
+            },
+            None => Vec::new(),
+        },
+    }
+}
+
+// ISO 8601 in UTC, e.g. 2019-10-18T04:05:06Z
+fn format_time(time: &Timestamp) -> String {
+    match broken_down_time(time, true) {
+        Some(tm) => format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            tm.tm_year as i64 + 1900,
+            tm.tm_mon + 1,
+            tm.tm_mday,
+            tm.tm_hour,
+            tm.tm_min,
+            tm.tm_sec
+        ),
+        None => String::new(),
+    }
+}
+
+// RFC 4180: enclose a field in double quotes if it contains a comma, a double quote or a line
+// break, and escape each double quote by preceding it with another double quote.
+fn write_csv_field(buffer: &mut Vec<u8>, field: &[u8]) {
+    if field
+        .iter()
+        .any(|&c| c == b',' || c == b'"' || c == b'\r' || c == b'\n')
+    {
+        buffer.push(b'"');
+        for &c in field {
+            if c == b'"' {
+                buffer.push(b'"');
+            }
+            buffer.push(c);
+        }
+        buffer.push(b'"');
+    } else {
+        buffer.extend_from_slice(field);
+    }
+}
+
+// Tabs and line breaks are not allowed in TSV fields, so escape them like the "text" format
+// of PostgreSQL and MySQL does.
+fn write_tsv_field(buffer: &mut Vec<u8>, field: &[u8]) {
+    for &c in field {
+        match c {
+            b'\\' => buffer.extend_from_slice(b"\\\\"),
+            b'\t' => buffer.extend_from_slice(b"\\t"),
+            b'\n' => buffer.extend_from_slice(b"\\n"),
+            b'\r' => buffer.extend_from_slice(b"\\r"),
+            _ => buffer.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn csv(field: &str) -> String {
+        let mut buffer = Vec::new();
+        write_csv_field(&mut buffer, field.as_bytes());
+        String::from_utf8(buffer).unwrap()
+    }
+
+    fn tsv(field: &str) -> String {
+        let mut buffer = Vec::new();
+        write_tsv_field(&mut buffer, field.as_bytes());
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn table_quoting() {
+        assert_eq!(csv("./a b"), "./a b");
+        assert_eq!(csv("./a,b"), "\"./a,b\"");
+        assert_eq!(csv("./a\"b\""), "\"./a\"\"b\"\"\"");
+        assert_eq!(csv("./a\nb"), "\"./a\nb\"");
+        assert_eq!(tsv("./a\tb\\c\nd"), "./a\\tb\\\\c\\nd");
+    }
+
+    #[test]
+    fn table_time() {
+        let time = Timestamp {
+            secs: 1571371506,
+            nsecs: 0,
+        };
+        assert_eq!(format_time(&time), "2019-10-18T04:05:06Z");
+    }
+}
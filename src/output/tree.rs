@@ -0,0 +1,164 @@
+use std::ffi::{OsStr, OsString};
+use std::io::{self, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+use super::super::internal::AppOptions;
+use super::super::walk::starting_point;
+use super::{add_path_terminator, check_path, check_result, format_path, Entry};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TreeStyle {
+    Unicode, // box-drawing characters
+    Ascii,
+}
+
+struct Connectors {
+    branch: &'static str,
+    last_branch: &'static str,
+    trunk: &'static str,
+    space: &'static str,
+}
+
+const UNICODE: Connectors = Connectors {
+    branch: "├── ",
+    last_branch: "└── ",
+    trunk: "│   ",
+    space: "    ",
+};
+
+const ASCII: Connectors = Connectors {
+    branch: "|-- ",
+    last_branch: "`-- ",
+    trunk: "|   ",
+    space: "    ",
+};
+
+struct Node {
+    name: OsString,
+    path: PathBuf,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(name: &OsStr, path: PathBuf) -> Node {
+        Node {
+            name: name.to_os_string(),
+            path,
+            children: Vec::new(),
+        }
+    }
+
+    // The entries are sorted by pathname, so the child to reuse is always the last one.
+    fn insert(&mut self, relative: &Path) {
+        let mut node = self;
+
+        for compo in relative.components() {
+            let name = compo.as_os_str();
+            let reuse = node
+                .children
+                .last()
+                .map_or(false, |child| child.name == name);
+
+            if !reuse {
+                let path = node.path.join(name);
+                node.children.push(Node::new(name, path));
+            }
+            node = { node }.children.last_mut().unwrap();
+        }
+    }
+}
+
+// Print the search results as indented trees, one tree for each starting point, showing only
+// the directories that lead to the results.
+//
+// The entries must have been sorted by pathname.
+pub fn print_tree(entries: impl Iterator<Item = Entry>, config: &AppOptions, style: TreeStyle) {
+    let mut trees: Vec<Node> = Vec::new();
+
+    for entry in entries {
+        if !entry.prints() {
+            continue;
+        }
+
+        let root = starting_point(&config.includes, &entry.path, entry.depth)
+            .unwrap_or_else(|| entry.path.parent().unwrap_or(Path::new("")));
+        let relative = entry.path.strip_prefix(root).unwrap_or(&entry.path);
+
+        match trees.iter().position(|tree| tree.path == root) {
+            Some(i) => trees[i].insert(relative),
+            None => {
+                let mut tree = Node::new(root.as_os_str(), root.to_path_buf());
+
+                tree.insert(relative);
+                trees.push(tree);
+            }
+        }
+    }
+
+    let connectors = match style {
+        TreeStyle::Unicode => &UNICODE,
+        TreeStyle::Ascii => &ASCII,
+    };
+
+    for tree in &trees {
+        let mut buffer = format_path(&tree.path, config);
+
+        check_path(&buffer, false);
+        add_path_terminator(&mut buffer, false);
+        check_result(io::stdout().write_all(buffer.as_slice()));
+
+        check_result(print_children(tree, config, connectors, &mut Vec::new()));
+    }
+}
+
+fn print_children(
+    node: &Node,
+    config: &AppOptions,
+    connectors: &Connectors,
+    prefix: &mut Vec<u8>,
+) -> io::Result<()> {
+    let stdout = io::stdout();
+    let count = node.children.len();
+
+    for (i, child) in node.children.iter().enumerate() {
+        let last = i + 1 == count;
+        let mut buffer = prefix.clone();
+
+        buffer.extend_from_slice(if last {
+            connectors.last_branch.as_bytes()
+        } else {
+            connectors.branch.as_bytes()
+        });
+
+        let name = child.name.as_bytes();
+        let style = config
+            .palette
+            .as_ref()
+            .and_then(|palette| palette.style_for_path(&child.path));
+
+        match style {
+            Some(style) => style
+                .to_ansi_term_style()
+                .paint(name)
+                .write_to(&mut buffer)?,
+            None => buffer.extend_from_slice(name),
+        }
+
+        check_path(&buffer, false);
+        add_path_terminator(&mut buffer, false);
+        stdout.lock().write_all(buffer.as_slice())?;
+
+        let size = prefix.len();
+
+        prefix.extend_from_slice(if last {
+            connectors.space.as_bytes()
+        } else {
+            connectors.trunk.as_bytes()
+        });
+        print_children(child, config, connectors, prefix)?;
+        prefix.truncate(size);
+    }
+
+    Ok(())
+}
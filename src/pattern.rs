@@ -103,7 +103,7 @@ impl PatternBuilder {
     // TODO: patch "regex" or "regex-syntax", or use another engine
     // The regex crate can't help much: https://github.com/rust-lang/regex/issues/426
     // The man asked my use case again and again, but I found that guy case-insensitive.
-    fn escape_pattern(pattern: &OsStr) -> Option<String> {
+    pub fn escape_pattern(pattern: &OsStr) -> Option<String> {
         let mut bytes = Vec::new();
 
         for c in pattern.as_bytes() {
@@ -0,0 +1,526 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::mem;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::PathBuf;
+
+use super::super::tempfile;
+
+use super::super::fileinfo::{FileInfo, Timestamp};
+use super::super::filter::Action;
+use super::super::internal::die;
+use super::super::output::Entry;
+
+// The number of runs merged at once before the end, and the most runs kept open at a time.
+// Some systems allow only 256 or even 64 open files per process by default.
+const FAN_IN: usize = 16;
+const MAX_RUNS: usize = 32;
+
+// A sorted run in a temporary file. Runs of higher levels are merged from more runs.
+struct Run {
+    level: usize,
+    file: File,
+}
+
+// A buffer of search results that spills sorted runs to temporary files when it grows beyond
+// the memory budget. The runs are merged at the end, so that the output is identical to the
+// stable sort of all search results in memory.
+pub struct Spiller {
+    budget: Option<usize>, // bytes
+    used: usize,
+    buffer: Vec<Entry>,
+    runs: Vec<Run>,
+}
+
+impl Spiller {
+    pub fn new(budget: Option<usize>) -> Spiller {
+        Spiller {
+            budget,
+            used: 0,
+            buffer: Vec::new(),
+            runs: Vec::new(),
+        }
+    }
+
+    pub fn push<F>(&mut self, entry: Entry, compare: F)
+    where
+        F: Fn(&Entry, &Entry) -> Ordering,
+    {
+        self.used += estimate_size(&entry);
+        self.buffer.push(entry);
+
+        if self.budget.map_or(false, |budget| self.used > budget) {
+            self.buffer.sort_by(&compare);
+
+            let run = write_run(self.buffer.drain(..));
+
+            self.spill(0, run);
+            self.used = 0;
+            self.compact(&compare);
+        }
+    }
+
+    fn spill(&mut self, level: usize, run: io::Result<File>) {
+        let file =
+            run.unwrap_or_else(|err| die(&format!("failed to spill search results: {}", err)));
+
+        self.runs.push(Run { level, file });
+    }
+
+    // Merge the last runs of the same level into a run of the next level, so that the number
+    // of open files stays small. Too many runs of different levels are merged anyway. Only
+    // adjacent runs are merged to keep the merge stable.
+    fn compact<F>(&mut self, compare: &F)
+    where
+        F: Fn(&Entry, &Entry) -> Ordering,
+    {
+        loop {
+            let count = self.runs.len();
+
+            if count < FAN_IN {
+                return;
+            }
+
+            let level = self.runs[count - 1].level;
+            let same_level = self.runs[count - FAN_IN..]
+                .iter()
+                .all(|run| run.level == level);
+
+            if !same_level && count < MAX_RUNS {
+                return;
+            }
+
+            let runs = self.runs.split_off(count - FAN_IN);
+            let level = runs.iter().map(|run| run.level).max().unwrap_or(0) + 1;
+            let runs = runs
+                .into_iter()
+                .map(|run| Box::new(RunReader::new(run.file)) as Box<dyn Iterator<Item = Entry>>)
+                .collect();
+            let run = write_run(Merger::new(runs, compare));
+
+            self.spill(level, run);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty() && self.runs.is_empty()
+    }
+
+    // Get all search results in sorted order.
+    pub fn into_sorted<'a, F>(self, compare: &'a F) -> Box<dyn Iterator<Item = Entry> + 'a>
+    where
+        F: Fn(&Entry, &Entry) -> Ordering,
+    {
+        let mut buffer = self.buffer;
+
+        // Stable sort is fast enough for nearly sorted items,
+        // although it uses 50% more memory than unstable sort.
+        // Stable sort is also needed for ordered actions.
+        // Would parallel sort really help much? Skeptical.
+        buffer.sort_by(compare);
+
+        if self.runs.is_empty() {
+            return Box::new(buffer.into_iter());
+        }
+
+        let mut runs: Vec<Box<dyn Iterator<Item = Entry>>> = self
+            .runs
+            .into_iter()
+            .map(|run| Box::new(RunReader::new(run.file)) as Box<dyn Iterator<Item = Entry>>)
+            .collect();
+
+        // the search results in memory were received last
+        runs.push(Box::new(buffer.into_iter()));
+
+        Box::new(Merger::new(runs, compare))
+    }
+}
+
+// A rough estimate of the memory used by a search result.
+fn estimate_size(entry: &Entry) -> usize {
+    mem::size_of::<Entry>()
+        + entry.path.as_os_str().len()
+        + entry.actions.capacity() * mem::size_of::<Action>()
+        + if entry.info.is_some() {
+            mem::size_of::<FileInfo>()
+        } else {
+            0
+        }
+}
+
+fn write_run<I>(entries: I) -> io::Result<File>
+where
+    I: IntoIterator<Item = Entry>,
+{
+    let mut file = tempfile::tempfile()?;
+
+    {
+        let mut writer = BufWriter::new(&mut file);
+
+        for entry in entries {
+            write_entry(&mut writer, &entry)?;
+        }
+        writer.flush()?;
+    }
+    file.seek(SeekFrom::Start(0))?;
+
+    Ok(file)
+}
+
+// Only the search results written by this process are read back, so the byte order of the
+// host is used.
+fn write_entry(writer: &mut impl Write, entry: &Entry) -> io::Result<()> {
+    let path = entry.path.as_os_str().as_bytes();
+
+    writer.write_all(&(path.len() as u64).to_ne_bytes())?;
+    writer.write_all(path)?;
+    writer.write_all(&(entry.depth as u64).to_ne_bytes())?;
+
+    match entry.info {
+        Some(ref info) => {
+            writer.write_all(&[1])?;
+            writer.write_all(&info.ino.to_ne_bytes())?;
+            writer.write_all(&info.mode.to_ne_bytes())?;
+            writer.write_all(&info.nlink.to_ne_bytes())?;
+            writer.write_all(&info.uid.to_ne_bytes())?;
+            writer.write_all(&info.gid.to_ne_bytes())?;
+            writer.write_all(&info.size.to_ne_bytes())?;
+            writer.write_all(&info.blocks.to_ne_bytes())?;
+            for time in &[info.atime, info.mtime, info.ctime] {
+                writer.write_all(&time.secs.to_ne_bytes())?;
+                writer.write_all(&time.nsecs.to_ne_bytes())?;
+            }
+        }
+        None => writer.write_all(&[0])?,
+    }
+
+    writer.write_all(&(entry.actions.len() as u32).to_ne_bytes())?;
+    for action in &entry.actions {
+        writer.write_all(&[action_code(action)])?;
+    }
+
+    Ok(())
+}
+
+fn read_entry(reader: &mut impl Read) -> io::Result<Option<Entry>> {
+    let mut u64_buf = [0; 8];
+    let mut u32_buf = [0; 4];
+    let mut u8_buf = [0; 1];
+
+    // end of file
+    match reader.read(&mut u64_buf[..1])? {
+        0 => return Ok(None),
+        _ => reader.read_exact(&mut u64_buf[1..])?,
+    }
+
+    let mut path = vec![0; u64::from_ne_bytes(u64_buf) as usize];
+
+    reader.read_exact(&mut path)?;
+
+    let mut read_u64 = |reader: &mut dyn Read| {
+        reader
+            .read_exact(&mut u64_buf)
+            .map(|_| u64::from_ne_bytes(u64_buf))
+    };
+    let mut read_u32 = |reader: &mut dyn Read| {
+        reader
+            .read_exact(&mut u32_buf)
+            .map(|_| u32::from_ne_bytes(u32_buf))
+    };
+
+    let depth = read_u64(reader)? as usize;
+
+    reader.read_exact(&mut u8_buf)?;
+
+    let info = if u8_buf[0] != 0 {
+        let ino = read_u64(reader)?;
+        let mode = read_u32(reader)?;
+        let nlink = read_u64(reader)?;
+        let uid = read_u32(reader)?;
+        let gid = read_u32(reader)?;
+        let size = read_u64(reader)?;
+        let blocks = read_u64(reader)?;
+        let mut times = [Timestamp { secs: 0, nsecs: 0 }; 3];
+
+        for time in times.iter_mut() {
+            time.secs = read_u64(reader)? as i64;
+            time.nsecs = read_u64(reader)? as i64;
+        }
+
+        Some(FileInfo {
+            ino,
+            mode,
+            nlink,
+            uid,
+            gid,
+            size,
+            blocks,
+            atime: times[0],
+            mtime: times[1],
+            ctime: times[2],
+        })
+    } else {
+        None
+    };
+
+    let mut actions = vec![0; read_u32(reader)? as usize];
+
+    reader.read_exact(&mut actions)?;
+
+    Ok(Some(Entry {
+        path: PathBuf::from(OsString::from_vec(path)),
+        depth,
+        info,
+        actions: actions
+            .into_iter()
+            .map(code_action)
+            .collect::<io::Result<_>>()?,
+    }))
+}
+
+fn action_code(action: &Action) -> u8 {
+    match action {
+        Action::Print => 0,
+        Action::Print0 => 1,
+        Action::Ls => 2,
+        Action::Prune => 3,
+        Action::Quit => 4,
+    }
+}
+
+fn code_action(code: u8) -> io::Result<Action> {
+    match code {
+        0 => Ok(Action::Print),
+        1 => Ok(Action::Print0),
+        2 => Ok(Action::Ls),
+        3 => Ok(Action::Prune),
+        4 => Ok(Action::Quit),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("found invalid action code {}", code),
+        )),
+    }
+}
+
+struct RunReader {
+    reader: BufReader<File>,
+}
+
+impl RunReader {
+    fn new(file: File) -> RunReader {
+        RunReader {
+            reader: BufReader::new(file),
+        }
+    }
+}
+
+impl Iterator for RunReader {
+    type Item = Entry;
+
+    fn next(&mut self) -> Option<Entry> {
+        read_entry(&mut self.reader)
+            .unwrap_or_else(|err| die(&format!("failed to read spilled search results: {}", err)))
+    }
+}
+
+// The head of a sorted run.
+struct Head<'a, F: 'a> {
+    entry: Entry,
+    run: usize,
+    compare: &'a F,
+}
+
+impl<'a, F> Ord for Head<'a, F>
+where
+    F: Fn(&Entry, &Entry) -> Ordering,
+{
+    // BinaryHeap is a max-heap, so the order is reversed. The earlier run goes first for equal
+    // search results to keep the merge stable.
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.compare)(&other.entry, &self.entry).then_with(|| other.run.cmp(&self.run))
+    }
+}
+
+impl<'a, F> PartialOrd for Head<'a, F>
+where
+    F: Fn(&Entry, &Entry) -> Ordering,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, F> PartialEq for Head<'a, F>
+where
+    F: Fn(&Entry, &Entry) -> Ordering,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<'a, F> Eq for Head<'a, F> where F: Fn(&Entry, &Entry) -> Ordering {}
+
+// K-way merge of sorted runs.
+struct Merger<'a, F: 'a> {
+    runs: Vec<Box<dyn Iterator<Item = Entry>>>,
+    heap: BinaryHeap<Head<'a, F>>,
+}
+
+impl<'a, F> Merger<'a, F>
+where
+    F: Fn(&Entry, &Entry) -> Ordering,
+{
+    fn new(mut runs: Vec<Box<dyn Iterator<Item = Entry>>>, compare: &'a F) -> Merger<'a, F> {
+        let mut heap = BinaryHeap::with_capacity(runs.len());
+
+        for (run, entries) in runs.iter_mut().enumerate() {
+            if let Some(entry) = entries.next() {
+                heap.push(Head {
+                    entry,
+                    run,
+                    compare,
+                });
+            }
+        }
+
+        Merger { runs, heap }
+    }
+}
+
+impl<'a, F> Iterator for Merger<'a, F>
+where
+    F: Fn(&Entry, &Entry) -> Ordering,
+{
+    type Item = Entry;
+
+    fn next(&mut self) -> Option<Entry> {
+        let head = self.heap.pop()?;
+
+        if let Some(entry) = self.runs[head.run].next() {
+            self.heap.push(Head {
+                entry,
+                run: head.run,
+                compare: head.compare,
+            });
+        }
+
+        Some(head.entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, depth: usize) -> Entry {
+        Entry {
+            path: PathBuf::from(path),
+            depth,
+            info: None,
+            actions: vec![Action::Print],
+        }
+    }
+
+    #[test]
+    fn merge_entry_roundtrip() {
+        let time = Timestamp {
+            secs: -1,
+            nsecs: 999_999_999,
+        };
+        let mut value = entry("./a\nb", 1);
+
+        value.info = Some(FileInfo {
+            ino: 1,
+            mode: 0o100644,
+            nlink: 2,
+            uid: 3,
+            gid: 4,
+            size: 5,
+            blocks: 6,
+            atime: time,
+            mtime: time,
+            ctime: time,
+        });
+        value.actions = vec![Action::Ls, Action::Prune, Action::Print0];
+
+        let mut bytes = Vec::new();
+
+        write_entry(&mut bytes, &value).unwrap();
+        write_entry(&mut bytes, &entry("./c", 1)).unwrap();
+
+        let mut reader = &bytes[..];
+        let first = read_entry(&mut reader).unwrap().unwrap();
+        let second = read_entry(&mut reader).unwrap().unwrap();
+
+        assert_eq!(first.path, value.path);
+        assert_eq!(first.depth, 1);
+        assert_eq!(first.actions, value.actions);
+        assert_eq!(
+            first.info.map(|info| (info.mode, info.mtime)),
+            Some((0o100644, time))
+        );
+        assert_eq!(second.path, PathBuf::from("./c"));
+        assert!(second.info.is_none());
+        assert!(read_entry(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn merge_stable() {
+        // compare by depth only
+        let compare = |a: &Entry, b: &Entry| a.depth.cmp(&b.depth);
+        let mut spiller = Spiller::new(Some(1));
+
+        for (i, depth) in [3, 1, 2, 1, 3, 2, 1].iter().enumerate() {
+            spiller.push(entry(&format!("{}", i), *depth), &compare);
+        }
+        assert_eq!(spiller.runs.len(), 7);
+
+        let paths = spiller
+            .into_sorted(&compare)
+            .map(|entry| entry.path.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+
+        assert_eq!(paths, ["1", "3", "6", "2", "5", "0", "4"]);
+    }
+
+    #[test]
+    fn merge_many_runs() {
+        let compare = |a: &Entry, b: &Entry| a.depth.cmp(&b.depth);
+        let mut spiller = Spiller::new(Some(1));
+        let mut expected = Vec::new();
+
+        for i in 0..1000 {
+            spiller.push(entry(&format!("{}", i), i * 7 % 13), &compare);
+            expected.push(entry(&format!("{}", i), i * 7 % 13));
+            assert!(spiller.runs.len() < MAX_RUNS);
+        }
+        expected.sort_by(&compare);
+
+        let paths = spiller
+            .into_sorted(&compare)
+            .map(|entry| entry.path)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            paths,
+            expected
+                .into_iter()
+                .map(|entry| entry.path)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn merge_invalid_action() {
+        let mut bytes = Vec::new();
+
+        write_entry(&mut bytes, &entry("./a", 1)).unwrap();
+        *bytes.last_mut().unwrap() = 0xFF;
+
+        assert!(read_entry(&mut &bytes[..]).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+mod merge;
+
+use std::cmp::Ordering;
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+
+use super::fileinfo::FileInfo;
+use super::internal::{AppOptions, Error};
+use super::output::Entry;
+
+pub use self::merge::Spiller;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortKey {
+    Path,    // byte strings of path components
+    Size,    // file size
+    Mtime,   // last modification time
+    Atime,   // last access time
+    Ctime,   // last status change time
+    Name,    // file name
+    Ext,     // file extension
+    Version, // file name with numbers compared by value
+    Depth,   // search depth
+}
+
+impl SortKey {
+    pub fn from_str(name: &OsStr) -> Result<SortKey, Error> {
+        match name.as_bytes() {
+            b"path" => Ok(SortKey::Path),
+            b"size" => Ok(SortKey::Size),
+            b"mtime" => Ok(SortKey::Mtime),
+            b"atime" => Ok(SortKey::Atime),
+            b"ctime" => Ok(SortKey::Ctime),
+            b"name" => Ok(SortKey::Name),
+            b"ext" => Ok(SortKey::Ext),
+            b"version" => Ok(SortKey::Version),
+            b"depth" => Ok(SortKey::Depth),
+            _ => Err(Error::from_str(&format!(
+                "found unrecognized sort key {:?}",
+                name
+            ))),
+        }
+    }
+
+    pub fn needs_metadata(&self) -> bool {
+        match self {
+            SortKey::Size | SortKey::Mtime | SortKey::Atime | SortKey::Ctime => true,
+            _ => false,
+        }
+    }
+}
+
+// Compare the search results by the sort keys in order, and then by pathname.
+// Results without metadata come first.
+pub fn compare(a: &Entry, b: &Entry, config: &AppOptions) -> Ordering {
+    let ordering = config
+        .sort_keys
+        .iter()
+        .fold(Ordering::Equal, |ordering, key| {
+            ordering.then_with(|| compare_by(a, b, *key))
+        })
+        .then_with(|| a.path.cmp(&b.path));
+
+    if config.reverse {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+fn compare_by(a: &Entry, b: &Entry, key: SortKey) -> Ordering {
+    match key {
+        SortKey::Path => a.path.cmp(&b.path),
+        SortKey::Size => cmp_info(a, b, |info| info.size),
+        SortKey::Mtime => cmp_info(a, b, |info| info.mtime),
+        SortKey::Atime => cmp_info(a, b, |info| info.atime),
+        SortKey::Ctime => cmp_info(a, b, |info| info.ctime),
+        SortKey::Name => name(a).cmp(&name(b)),
+        SortKey::Ext => ext(a).cmp(&ext(b)),
+        SortKey::Version => match (name(a), name(b)) {
+            (Some(x), Some(y)) => compare_version(x, y),
+            (x, y) => x.cmp(&y),
+        },
+        SortKey::Depth => a.depth.cmp(&b.depth),
+    }
+}
+
+fn name(entry: &Entry) -> Option<&[u8]> {
+    entry.path.file_name().map(OsStr::as_bytes)
+}
+
+fn ext(entry: &Entry) -> Option<&[u8]> {
+    entry.path.extension().map(OsStr::as_bytes)
+}
+
+fn cmp_info<T: Ord>(a: &Entry, b: &Entry, field: impl Fn(&FileInfo) -> T) -> Ordering {
+    a.info
+        .as_ref()
+        .map(&field)
+        .cmp(&b.info.as_ref().map(&field))
+}
+
+// Natural order like `ls -v`, e.g. a2.txt < a10.txt < b1.txt
+fn compare_version(a: &[u8], b: &[u8]) -> Ordering {
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() && j < b.len() {
+        if a[i].is_ascii_digit() && b[j].is_ascii_digit() {
+            let (x, next_i) = number(a, i);
+            let (y, next_j) = number(b, j);
+            let ordering = x.len().cmp(&y.len()).then_with(|| x.cmp(y));
+
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            i = next_i;
+            j = next_j;
+        } else {
+            if a[i] != b[j] {
+                return a[i].cmp(&b[j]);
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+
+    (a.len() - i).cmp(&(b.len() - j)).then_with(|| a.cmp(b))
+}
+
+// Get the digits without leading zeros, and the position after the number.
+fn number(bytes: &[u8], start: usize) -> (&[u8], usize) {
+    let end = bytes[start..]
+        .iter()
+        .position(|c| !c.is_ascii_digit())
+        .map_or(bytes.len(), |len| start + len);
+    let digits = &bytes[start..end];
+    let zeros = digits.iter().take_while(|&&c| c == b'0').count();
+
+    (&digits[zeros..], end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(a: &str, b: &str) -> Ordering {
+        compare_version(a.as_bytes(), b.as_bytes())
+    }
+
+    #[test]
+    fn sort_version() {
+        assert_eq!(version("a2.txt", "a10.txt"), Ordering::Less);
+        assert_eq!(version("a10.txt", "b1.txt"), Ordering::Less);
+        assert_eq!(version("v1.9.0", "v1.10.0"), Ordering::Less);
+        assert_eq!(version("file", "file1"), Ordering::Less);
+        assert_eq!(version("a007", "a7"), Ordering::Less);
+        assert_eq!(version("a7", "a7"), Ordering::Equal);
+        assert_eq!(version("a.b", "a1"), Ordering::Less);
+    }
+}
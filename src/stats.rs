@@ -0,0 +1,114 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Instant;
+
+use super::log::{self, Level, LevelFilter, Log, Metadata, Record};
+
+use super::output::Entry;
+
+// The entries skipped by the walker for ignore files or hidden files are only reported in its
+// debug logs, e.g. "ignoring ./target: Ignore(...)", so they are counted by a logger.
+static IGNORED: AtomicUsize = AtomicUsize::new(0);
+
+struct IgnoreLogger;
+
+static LOGGER: IgnoreLogger = IgnoreLogger;
+
+impl Log for IgnoreLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() == Level::Debug && metadata.target() == "ignore::walk"
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) && record.args().to_string().starts_with("ignoring ") {
+            IGNORED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+// Totals of a search shared by all threads.
+pub struct Stats {
+    start: Instant,
+    matches: AtomicUsize,
+    directories: AtomicUsize,
+    skipped: AtomicUsize,
+    errors: AtomicUsize,
+    bytes: AtomicU64,
+}
+
+impl Stats {
+    pub fn new() -> Stats {
+        Stats {
+            start: Instant::now(),
+            matches: AtomicUsize::new(0),
+            directories: AtomicUsize::new(0),
+            skipped: AtomicUsize::new(0),
+            errors: AtomicUsize::new(0),
+            bytes: AtomicU64::new(0),
+        }
+    }
+
+    pub fn matches(&self) -> usize {
+        self.matches.load(Ordering::Relaxed)
+    }
+
+    // Only the search results to be printed are counted, as well as the sizes of regular files.
+    pub fn add_match(&self, entry: &Entry) {
+        if entry.prints() {
+            self.matches.fetch_add(1, Ordering::Relaxed);
+
+            if let Some(ref info) = entry.info {
+                if info.is_file() {
+                    self.bytes.fetch_add(info.size, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    pub fn add_directory(&self) {
+        self.directories.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_skipped(&self) {
+        self.skipped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Count the entries skipped by the walker as well. Only one logger can be set.
+    pub fn count_ignored(&self) {
+        if log::set_logger(&LOGGER).is_ok() {
+            log::set_max_level(LevelFilter::Debug);
+        }
+    }
+
+    fn skipped(&self) -> usize {
+        self.skipped.load(Ordering::Relaxed) + IGNORED.load(Ordering::Relaxed)
+    }
+
+    pub fn add_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn print(&self) {
+        let elapsed = self.start.elapsed();
+
+        write!(
+            &mut io::stderr(),
+            "matches: {}\n\
+             directories: {}\n\
+             skipped: {}\n\
+             errors: {}\n\
+             bytes: {}\n\
+             elapsed: {}.{:03}s\n",
+            self.matches(),
+            self.directories.load(Ordering::Relaxed),
+            self.skipped(),
+            self.errors.load(Ordering::Relaxed),
+            self.bytes.load(Ordering::Relaxed),
+            elapsed.as_secs(),
+            elapsed.subsec_millis()
+        )
+        .expect("write to stderr");
+    }
+}
@@ -1,7 +1,10 @@
-use std::io;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Write};
 use std::option::Option;
+use std::os::unix::fs::MetadataExt;
 use std::os::unix::io::AsRawFd;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 use std::sync::atomic::{self, AtomicUsize};
 use std::sync::mpsc;
@@ -15,9 +18,13 @@ use super::signal_hook;
 
 use super::counter::Counter;
 use super::exec;
+use super::fileinfo::FileInfo;
 use super::filter::Action;
+use super::fshelper::resolve_path;
 use super::internal::{die, error, warn, AppOptions};
-use super::output;
+use super::output::{self, OutputFormat};
+use super::sort::{self, Spiller};
+use super::stats::Stats;
 
 const MAX_CNT: usize = 500;
 
@@ -33,9 +40,44 @@ enum ReceiverMode {
     Streaming,             // Receiver is directly printing search results to the output.
 }
 
+// The states shared by all sender threads.
+struct SenderState {
+    // The number of search results found.
+    found: AtomicUsize,
+
+    // The maximum number of search results to find.
+    max_results: Option<usize>,
+
+    // The device numbers and inode numbers of search results found.
+    seen: Mutex<HashSet<(u64, u64)>>,
+}
+
 pub struct DirEntry<'a> {
     pub path: &'a Path,
-    pub file_type: Option<std::fs::FileType>,
+    pub depth: usize,
+    pub file_type: Option<fs::FileType>,
+}
+
+impl<'a> DirEntry<'a> {
+    // Symlinks are not followed unless they are traversed by the walker.
+    pub fn metadata(&self, config: &AppOptions) -> Option<fs::Metadata> {
+        if config.follow_symlink {
+            if let Ok(meta) = self.path.metadata() {
+                return Some(meta);
+            }
+        }
+        self.path.symlink_metadata().ok()
+    }
+}
+
+// Find out which starting point produced the search result.
+pub fn starting_point<'a>(includes: &'a [PathBuf], path: &Path, depth: usize) -> Option<&'a Path> {
+    let count = path.components().count();
+
+    includes
+        .iter()
+        .find(|root| path.starts_with(root) && root.components().count() + depth == count)
+        .map(|root| root.as_path())
 }
 
 fn exit_if_sigint(quitting: &Arc<AtomicUsize>) {
@@ -46,6 +88,28 @@ fn exit_if_sigint(quitting: &Arc<AtomicUsize>) {
     }
 }
 
+// Whether all search results are buffered before output.
+fn buffer_all(config: &AppOptions) -> bool {
+    match config.output_format {
+        OutputFormat::Tree(_) => true,
+        _ => !config.sort_keys.is_empty(),
+    }
+}
+
+// Count down the remaining number of search results for output.
+fn within_limit(remaining: &mut Option<usize>, value: &output::Entry) -> bool {
+    match remaining {
+        Some(0) => false,
+        Some(ref mut num) => {
+            if value.prints() {
+                *num -= 1;
+            }
+            true
+        }
+        None => true,
+    }
+}
+
 fn calc_send_threads(threads: usize, sort_path: bool) -> usize {
     if sort_path {
         (threads - 1).max(1) // minus receiver thread
@@ -100,7 +164,7 @@ fn spawn_receiver_threads(
             None
         };
 
-        let threads = calc_recv_threads(config.threads, config.sort_path);
+        let threads = calc_recv_threads(config.threads, buffer_all(&config) || config.ordered);
 
         let cmd = Arc::new(cmd.clone());
         // Enable caching for broadcast, as interactive input may not satisfy all commands.
@@ -132,10 +196,197 @@ fn spawn_receiver_threads(
     Vec::new()
 }
 
+// Convert the entry from the walker for filtering.
+fn send_entry(
+    entry_o: &Result<ignore::DirEntry, ignore::Error>,
+    tx: &mpsc::Sender<output::Entry>,
+    config: &AppOptions,
+    stats: &Stats,
+    shared: &SenderState,
+) -> WalkState {
+    // https://docs.rs/ignore/0.4.6/ignore/struct.DirEntry.html
+    let entry = match entry_o {
+        Ok(ref entry) => {
+            // IDEA: breaking change: include root dir?
+            // will traverse symlinks
+            if entry.depth() != 0 || !entry.path().is_dir() {
+                DirEntry {
+                    path: entry.path(),
+                    depth: entry.depth(),
+                    file_type: entry.file_type(),
+                }
+            } else {
+                stats.add_directory();
+                return WalkState::Continue;
+            }
+        }
+        Err(ref err) => {
+            let mut problematic_entry = None;
+
+            stats.add_error();
+
+            // https://docs.rs/walkdir/2.2.6/walkdir/struct.WalkDir.html#method.follow_links
+            // > If a symbolic link is broken or is involved in a loop, an error is yielded.
+            let (depth, err) = match err {
+                ignore::Error::WithDepth { depth, err } => (*depth, &**err),
+                _ => (0, err),
+            };
+
+            if let ignore::Error::WithPath { path, err: cause } = err {
+                if !err.is_partial() {
+                    let problematic = if !path.exists() {
+                        true // Other than symlinks, what may not exist?
+                    } else if let ignore::Error::Io(ref cause) = **cause {
+                        // TODO: need to suppress some warnings from deps
+                        //       mkdir -m 000 entrance
+                        if cause.kind() == io::ErrorKind::PermissionDenied
+                            // traverse symlink
+                            && path.is_dir()
+                        {
+                            warn(&format!("could not open directory {:?}: {}", path, cause));
+                            true
+                        } else {
+                            false
+                        }
+                    } else {
+                        false
+                    };
+
+                    if problematic {
+                        let file_type = path.symlink_metadata().map(|meta| meta.file_type()).ok();
+
+                        problematic_entry = Some(DirEntry {
+                            path,
+                            depth,
+                            file_type,
+                        });
+                    }
+                }
+            }
+
+            if problematic_entry.is_some() {
+                problematic_entry.unwrap()
+            } else {
+                if !err.is_partial() || config.verbose {
+                    warn(&err);
+                }
+                if err.is_partial() {
+                    return WalkState::Continue;
+                } else {
+                    return WalkState::Skip;
+                }
+            }
+        }
+    };
+
+    filter_entry(entry, tx, config, stats, shared)
+}
+
+// Filter the file path and send it to the sorter thread if it is a search result.
+fn filter_entry(
+    entry: DirEntry,
+    tx: &mpsc::Sender<output::Entry>,
+    config: &AppOptions,
+    stats: &Stats,
+    shared: &SenderState,
+) -> WalkState {
+    // do not check duplicates nor relative/absolute paths
+    if !config.excludes.is_empty() {
+        let resolved = resolve_path(entry.path, config.path_resolution);
+
+        for path in &config.excludes {
+            // TODO: patch regex/globset and allow new(OsStr)
+            if resolved == path.as_path() {
+                stats.add_skipped();
+                return WalkState::Skip;
+            }
+        }
+    }
+    if !config.exclude_patterns.is_empty() && config.exclude_patterns.is_match(entry.path) {
+        stats.add_skipped();
+        return WalkState::Skip;
+    }
+
+    if entry.file_type.map_or(false, |ftype| ftype.is_dir()) {
+        stats.add_directory();
+    }
+
+    if shared.max_results.map_or(false, |max| {
+        shared.found.load(atomic::Ordering::Relaxed) >= max
+    }) {
+        return WalkState::Quit;
+    }
+
+    let actions = config.filter.apply(&entry, &config);
+
+    if !actions.is_empty() {
+        let mut state = if actions.contains(&Action::Quit) {
+            WalkState::Quit
+        } else if actions.contains(&Action::Prune) {
+            stats.add_skipped();
+            WalkState::Skip
+        } else {
+            WalkState::Continue
+        };
+
+        let needs_info = output::needs_metadata(&config)
+            || config.stats
+            || config.sort_keys.iter().any(|key| key.needs_metadata())
+            || actions.contains(&Action::Ls);
+        let meta = if needs_info || config.unique {
+            entry.metadata(&config)
+        } else {
+            None
+        };
+
+        if config.unique {
+            if let Some(ref meta) = meta {
+                if !shared.seen.lock().unwrap().insert((meta.dev(), meta.ino())) {
+                    return state; // reached through another path
+                }
+            }
+        }
+
+        let info = if needs_info {
+            meta.map(|meta| FileInfo::from_metadata(&meta))
+        } else {
+            None
+        };
+        let value = output::Entry {
+            path: entry.path.to_owned(),
+            depth: entry.depth,
+            info,
+            actions,
+        };
+
+        if let Some(max) = shared.max_results {
+            if value.prints() {
+                let count = shared.found.fetch_add(1, atomic::Ordering::Relaxed) + 1;
+
+                if count > max {
+                    return WalkState::Quit;
+                } else if count == max {
+                    state = WalkState::Quit;
+                }
+            }
+        }
+
+        if tx.send(value).is_err() {
+            error("sender thread failed to send data");
+            return WalkState::Quit;
+        }
+
+        return state;
+    }
+
+    WalkState::Continue
+}
+
 fn spawn_sender_threads(
     tx: mpsc::Sender<output::Entry>,
     config: Arc<AppOptions>,
     quitting: Arc<AtomicUsize>,
+    stats: Arc<Stats>,
 ) {
     let mut builder = WalkBuilder::new(&config.includes[0]);
 
@@ -144,17 +395,143 @@ fn spawn_sender_threads(
         builder.add(path);
     }
 
-    let walker = builder
+    let shared = Arc::new(SenderState {
+        found: AtomicUsize::new(0),
+        // All search results are needed for getting the first results in order.
+        max_results: if buffer_all(&config) {
+            None
+        } else {
+            config.max_results
+        },
+        seen: Mutex::new(HashSet::new()),
+    });
+
+    if let Some(ref paths) = config.listed_paths {
+        let mut tx_counter = Counter::new(MAX_CNT, Some(Arc::clone(&quitting)));
+        let root = &config.includes[0];
+
+        for path in paths {
+            if tx_counter.inc() {
+                error("sender thread aborted");
+                break;
+            }
+
+            // the depth relative to the current working directory if possible
+            let depth = match path.strip_prefix(root) {
+                Ok(rest) => rest.components().count(),
+                Err(_) => path.components().count().saturating_sub(1),
+            };
+
+            // the excluded directories are not walked, so check the ancestors as well
+            let excluded = !config.excludes.is_empty() && {
+                let resolved = resolve_path(path, config.path_resolution);
+
+                config.excludes.iter().any(|dir| resolved.starts_with(dir))
+            };
+            let excluded = excluded
+                || !config.exclude_patterns.is_empty()
+                    && path
+                        .ancestors()
+                        .skip(1)
+                        .take(depth.saturating_sub(1))
+                        .any(|dir| config.exclude_patterns.is_match(dir));
+
+            if excluded {
+                stats.add_skipped();
+                continue;
+            }
+
+            let meta = if config.follow_symlink {
+                path.metadata().or_else(|_| path.symlink_metadata())
+            } else {
+                path.symlink_metadata()
+            };
+            let file_type = match meta {
+                Ok(meta) => Some(meta.file_type()),
+                Err(err) => {
+                    stats.add_error();
+                    warn(&format!("could not access {:?}: {}", path, err));
+                    continue;
+                }
+            };
+            let entry = DirEntry {
+                path,
+                depth,
+                file_type,
+            };
+
+            if let WalkState::Quit = filter_entry(entry, &tx, &config, &stats, &shared) {
+                break;
+            }
+        }
+
+        return;
+    }
+
+    if config.read_ignore {
+        builder.add_custom_ignore_filename(".ffignore");
+
+        for path in &config.ignore_files {
+            if let Some(err) = builder.add_ignore(path) {
+                warn(&format!("failed to read ignore file {:?}: {}", path, err));
+            }
+        }
+    }
+
+    builder
         .hidden(!config.dot_files)
         .ignore(config.read_ignore)
-        .git_ignore(config.read_ignore)
-        .parents(config.read_ignore)
-        .git_global(config.read_ignore)
-        .git_exclude(config.read_ignore)
+        .git_ignore(config.read_vcs_ignore)
+        .parents(config.read_parent_ignore)
+        .git_global(config.read_global_ignore)
+        .git_exclude(config.read_vcs_ignore)
         .same_file_system(config.same_file_system)
         .follow_links(config.follow_symlink)
-        .max_depth(config.max_depth)
-        .threads(calc_send_threads(config.threads, config.sort_path))
+        .max_depth(config.max_depth);
+
+    if config.ordered {
+        // The entries of each directory are sorted by file name, so the search results are
+        // produced in sorted order as soon as each directory is read.
+        // The sequential walker only skips directories by a filter, so the last directory
+        // pruned or excluded is shared with the filter, which then stops the walker from
+        // descending into it.
+        let pruned: Arc<Mutex<Option<PathBuf>>> = Arc::new(Mutex::new(None));
+        let walker = {
+            let pruned = Arc::clone(&pruned);
+
+            builder
+                .sort_by_file_name(|a, b| a.cmp(b))
+                .filter_entry(move |entry| {
+                    pruned
+                        .lock()
+                        .unwrap()
+                        .as_ref()
+                        .map_or(true, |dir| !entry.path().starts_with(dir))
+                })
+                .build()
+        };
+        let mut tx_counter = Counter::new(MAX_CNT, Some(Arc::clone(&quitting)));
+
+        for entry_o in walker {
+            if tx_counter.inc() {
+                error("sender thread aborted");
+                break;
+            }
+
+            match send_entry(&entry_o, &tx, &config, &stats, &shared) {
+                WalkState::Quit => break,
+                WalkState::Skip => {
+                    *pruned.lock().unwrap() = entry_o.ok().map(|entry| entry.into_path())
+                }
+                WalkState::Continue => (),
+            }
+        }
+
+        return;
+    }
+
+    let walker = builder
+        .threads(calc_send_threads(config.threads, buffer_all(&config)))
         // the non-parallel version can output first few sorted results earlier
         // and make less buffering but the total time used is 4 times longer
         .build_parallel();
@@ -164,6 +541,8 @@ fn spawn_sender_threads(
         let tx = tx.clone();
         let config = Arc::clone(&config);
         let quitting = Arc::clone(&quitting);
+        let stats = Arc::clone(&stats);
+        let shared = Arc::clone(&shared);
         let mut tx_counter = Counter::new(MAX_CNT, Some(quitting));
 
         Box::new(move |entry_o| {
@@ -172,104 +551,7 @@ fn spawn_sender_threads(
                 return WalkState::Quit;
             }
 
-            // https://docs.rs/ignore/0.4.6/ignore/struct.DirEntry.html
-            let entry = match entry_o {
-                Ok(ref entry) => {
-                    // IDEA: breaking change: include root dir?
-                    // will traverse symlinks
-                    if entry.depth() != 0 || !entry.path().is_dir() {
-                        DirEntry {
-                            path: entry.path(),
-                            file_type: entry.file_type(),
-                        }
-                    } else {
-                        return WalkState::Continue;
-                    }
-                }
-                Err(ref err) => {
-                    let mut problematic_entry = None;
-
-                    // https://docs.rs/walkdir/2.2.6/walkdir/struct.WalkDir.html#method.follow_links
-                    // > If a symbolic link is broken or is involved in a loop, an error is yielded.
-                    let err = match err {
-                        ignore::Error::WithDepth { depth: _, err } => err,
-                        _ => err,
-                    };
-                    if let ignore::Error::WithPath { path, err: cause } = err {
-                        if !err.is_partial() {
-                            let problematic = if !path.exists() {
-                                true // Other than symlinks, what may not exist?
-                            } else if let ignore::Error::Io(ref cause) = **cause {
-                                // TODO: need to suppress some warnings from deps
-                                //       mkdir -m 000 entrance
-                                if cause.kind() == io::ErrorKind::PermissionDenied
-                                    // traverse symlink
-                                    && path.is_dir()
-                                {
-                                    warn(&format!(
-                                        "could not open directory {:?}: {}",
-                                        path, cause
-                                    ));
-                                    true
-                                } else {
-                                    false
-                                }
-                            } else {
-                                false
-                            };
-
-                            if problematic {
-                                let file_type =
-                                    path.symlink_metadata().map(|meta| meta.file_type()).ok();
-
-                                problematic_entry = Some(DirEntry { path, file_type });
-                            }
-                        }
-                    }
-
-                    if problematic_entry.is_some() {
-                        problematic_entry.unwrap()
-                    } else {
-                        if !err.is_partial() || config.verbose {
-                            warn(&err);
-                        }
-                        if err.is_partial() {
-                            return WalkState::Continue;
-                        } else {
-                            return WalkState::Skip;
-                        }
-                    }
-                }
-            };
-
-            // do not check duplicates, relative/absolute paths nor real paths
-            for path in &config.excludes {
-                // TODO: patch regex/globset and allow new(OsStr)
-                if entry.path == path {
-                    return WalkState::Skip;
-                }
-            }
-
-            let actions = config.filter.apply(&entry, &config);
-
-            if !actions.is_empty() {
-                let state = if actions.contains(&Action::Quit) {
-                    WalkState::Quit
-                } else if actions.contains(&Action::Prune) {
-                    WalkState::Skip
-                } else {
-                    WalkState::Continue
-                };
-
-                if tx.send((entry.path.to_owned(), actions)).is_err() {
-                    error("sender thread failed to send data");
-                    return WalkState::Quit;
-                }
-
-                return state;
-            }
-
-            WalkState::Continue
+            send_entry(&entry_o, &tx, &config, &stats, &shared)
         })
     });
 
@@ -284,7 +566,13 @@ fn print_or_pipe(
     value: output::Entry,
     tx: &mpsc::Sender<output::Entry>,
     config: &Arc<AppOptions>,
+    stats: &Arc<Stats>,
 ) -> bool {
+    stats.add_match(&value);
+
+    if config.count {
+        return true; // counted only
+    }
     if print_mode {
         // NOTE: impossible to gracefully exit for `ff --color=always | break_pipe`
         output::print_entry(value, config);
@@ -303,20 +591,27 @@ fn spawn_sorter_thread(
     rx: mpsc::Receiver<output::Entry>,
     config: Arc<AppOptions>,
     quitting: Arc<AtomicUsize>,
+    stats: Arc<Stats>,
 ) -> thread::JoinHandle<()> {
     thread::spawn(move || {
         let print_mode = handles.is_empty();
 
+        if print_mode && !config.count {
+            output::print_header(&config);
+        }
+
         let max_buffer_time = if atty::is(atty::Stream::Stdout) {
             config.max_buffer_time.unwrap_or(100)
         } else {
             0
         };
 
-        let threads = calc_recv_threads(config.threads, config.sort_path);
+        let threads = calc_recv_threads(config.threads, buffer_all(&config) || config.ordered);
         let mut buffer = Vec::new();
-        let mut mode = if config.sort_path {
+        let mut mode = if buffer_all(&config) {
             ReceiverMode::Buffering(BufferTime::Eternity)
+        } else if config.ordered {
+            ReceiverMode::Streaming
         } else if max_buffer_time > 0 && (config.command.is_none() || threads == 1) {
             ReceiverMode::Buffering(BufferTime::Duration)
         } else {
@@ -325,14 +620,28 @@ fn spawn_sorter_thread(
 
         let mut rx_counter = Counter::new(MAX_CNT, Some(Arc::clone(&quitting)));
         let mut counter = Counter::new(MAX_CNT, None);
+        // the search results are counted after sorting if all of them are buffered
+        let mut remaining = if buffer_all(&config) {
+            None
+        } else {
+            config.max_results
+        };
         let start = time::Instant::now();
         let duration = time::Duration::from_millis(max_buffer_time);
+        let compare = |a: &output::Entry, b: &output::Entry| match config.output_format {
+            OutputFormat::Tree(_) => a.path.cmp(&b.path),
+            _ => sort::compare(a, b, &config),
+        };
+        let mut spiller = Spiller::new(config.sort_memory);
 
         for value in rx {
             if rx_counter.inc() {
                 error("sorter thread aborted");
                 return;
             }
+            if !within_limit(&mut remaining, &value) {
+                continue; // drain the channel for the sender threads
+            }
             match mode {
                 ReceiverMode::Buffering(buf_time) => match buf_time {
                     BufferTime::Duration => {
@@ -340,7 +649,7 @@ fn spawn_sorter_thread(
 
                         if counter.inc() && time::Instant::now() - start > duration {
                             for value in buffer.drain(0..) {
-                                if !print_or_pipe(print_mode, value, &tx, &config) {
+                                if !print_or_pipe(print_mode, value, &tx, &config, &stats) {
                                     return;
                                 }
                             }
@@ -348,30 +657,42 @@ fn spawn_sorter_thread(
                         }
                     }
                     BufferTime::Eternity => {
-                        buffer.push(value);
+                        spiller.push(value, &compare);
                     }
                 },
                 ReceiverMode::Streaming => {
-                    if !print_or_pipe(print_mode, value, &tx, &config) {
+                    if !print_or_pipe(print_mode, value, &tx, &config, &stats) {
                         return;
                     }
                 }
             }
         }
 
-        if !buffer.is_empty() {
-            // Stable sort is fast enough for nearly sorted items,
-            // although it uses 50% more memory than unstable sort.
-            // Stable sort is also needed for ordered actions.
-            // Would parallel sort really help much? Skeptical.
-            buffer.sort_by_key(|value| value.0.to_owned());
+        for value in buffer {
+            spiller.push(value, &compare);
+        }
 
-            for value in buffer {
+        if !spiller.is_empty() {
+            let mut remaining = config.max_results;
+            let values = spiller
+                .into_sorted(&compare)
+                .take_while(|value| within_limit(&mut remaining, value));
+
+            if let OutputFormat::Tree(style) = config.output_format {
+                output::print_tree(
+                    values.inspect(|value| stats.add_match(value)),
+                    &config,
+                    style,
+                );
+                return;
+            }
+
+            for value in values {
                 if rx_counter.inc() {
                     error("sorter thread aborted");
                     return;
                 }
-                if !print_or_pipe(print_mode, value, &tx, &config) {
+                if !print_or_pipe(print_mode, value, &tx, &config, &stats) {
                     return;
                 }
             }
@@ -417,14 +738,42 @@ pub fn scan(config: Arc<AppOptions>) {
 
     let handles = spawn_receiver_threads(xrx, Arc::clone(&config), Arc::clone(&quitting));
 
-    let handle = spawn_sorter_thread(handles, xtx, rx, Arc::clone(&config), Arc::clone(&quitting));
+    let stats = Arc::new(Stats::new());
+
+    if config.stats {
+        stats.count_ignored();
+    }
+
+    let handle = spawn_sorter_thread(
+        handles,
+        xtx,
+        rx,
+        Arc::clone(&config),
+        Arc::clone(&quitting),
+        Arc::clone(&stats),
+    );
 
     // blocking current thread because of WalkParallel::run()
-    spawn_sender_threads(tx, Arc::clone(&config), Arc::clone(&quitting));
+    spawn_sender_threads(
+        tx,
+        Arc::clone(&config),
+        Arc::clone(&quitting),
+        Arc::clone(&stats),
+    );
 
     if handle.join().is_err() {
         die("failed to process search results");
     }
 
+    if config.count {
+        let mut buffer = stats.matches().to_string().into_bytes();
+
+        output::add_path_terminator(&mut buffer, config.null_terminator);
+        output::check_result(io::stdout().write_all(buffer.as_slice()));
+    }
+    if config.stats {
+        stats.print();
+    }
+
     exit_if_sigint(&quitting);
 }
@@ -132,6 +132,30 @@ impl TestEnv {
         PathBuf::from(components.next().expect("root directory").as_os_str())
     }
 
+    // Get the output of calling *ff* with the specified arguments after asserting success.
+    pub fn get_output(&self, args: &[&str]) -> String {
+        self.get_outputs(args).0
+    }
+
+    // Get both stdout and stderr of calling *ff* with the specified arguments after asserting
+    // success.
+    pub fn get_outputs(&self, args: &[&str]) -> (String, String) {
+        let mut cmd = process::Command::new(&self.ff_exe);
+        cmd.current_dir(self.temp_dir.path());
+        cmd.args(args);
+
+        let output = cmd.output().expect("ff output");
+
+        if !output.status.success() {
+            panic!(format_exit_error(args, &output));
+        }
+
+        (
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        )
+    }
+
     // Assert that calling *ff* with the specified arguments produces the expected output.
     pub fn assert_output(&self, sort: bool, args: &[&str], expected: &str) {
         self.assert_output_subdirectory(sort, ".", args, expected)
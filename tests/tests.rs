@@ -5,6 +5,10 @@ extern crate regex;
 // TODO: Reorganize the test suit.
 mod testenv;
 
+use std::fs;
+use std::os::unix::fs::{symlink, MetadataExt};
+use std::process::Command;
+
 use regex::escape;
 
 use self::testenv::TestEnv;
@@ -409,225 +413,779 @@ fn test_no_ignore() {
     );
 }
 
+/// Ignore files (--ignore-file, .ffignore, --no-ignore-vcs, --no-ignore-parent)
 #[test]
-fn test_follow() {
+fn test_ignore_files() {
     let env = TestEnv::new();
+    let root = env.test_root();
+
+    fs::write(root.join("one/.ffignore"), "b.foo\nd.foo\n").unwrap();
+    fs::write(root.join("one/two/.gitignore"), "three\n").unwrap();
+    fs::create_dir(root.join("one/two/.git")).unwrap();
+    fs::write(root.join("patterns"), "c.foo\npatterns\n").unwrap();
 
     env.assert_output(
         true,
-        &["--follow"],
+        &["--regex", ".", "foo"],
         "./a.foo
-         ./α β
-         ./one
-         ./one.two
+         ./one/two/c.foo",
+    );
+    env.assert_output(
+        true,
+        &["--regex", ".", "foo", "--ignore-file", "patterns"],
+        "./a.foo",
+    );
+    env.assert_output(
+        true,
+        &["--regex", ".", "foo", "--no-ignore-vcs"],
+        "./a.foo
+         ./one/two/c.foo
+         ./one/two/three/directory_foo",
+    );
+    env.assert_output(
+        true,
+        &[
+            "--regex",
+            ".",
+            "foo",
+            "--no-ignore",
+            "--ignore-file",
+            "patterns",
+        ],
+        "./a.foo
+         ./ignored.foo
          ./one/b.foo
-         ./one/two
-         ./one/two/C.Foo2
          ./one/two/c.foo
-         ./one/two/three
          ./one/two/three/d.foo
-         ./one/two/three/directory_foo
-         ./symlink
-         ./symlink/C.Foo2
-         ./symlink/c.foo
-         ./symlink/three
-         ./symlink/three/d.foo
-         ./symlink/three/directory_foo
-         ./symlink2",
+         ./one/two/three/directory_foo",
     );
-
-    env.assert_output(
+    env.assert_output_subdirectory(
         true,
-        &["--regex", ".", "c.foo", "--follow"],
-        "./one/two/c.foo
-         ./symlink/c.foo",
+        "one/two/three",
+        &["--regex", ".", "foo"],
+        "./directory_foo",
+    );
+    env.assert_output_subdirectory(
+        true,
+        "one/two/three",
+        &["--regex", ".", "foo", "--no-ignore-parent"],
+        "./d.foo
+         ./directory_foo",
     );
 }
 
+/// Predicates of ignore rules (ignored, hidden)
 #[test]
-fn test_print0() {
+fn test_ignored_hidden() {
     let env = TestEnv::new();
+    let root = env.test_root();
+
+    fs::write(root.join("one/.ffignore"), "two/\n!d.foo\n").unwrap();
+    fs::write(root.join("patterns"), "/a.foo\none/b.foo\n").unwrap();
 
     env.assert_output(
         true,
-        &["--regex", ".", "foo", "--print0"],
-        "./a.fooNULL
-         ./one/b.fooNULL
-         ./one/two/c.fooNULL
-         ./one/two/three/d.fooNULL
-         ./one/two/three/directory_fooNULL",
+        &["--no-ignore", ".", "ignored", "print"],
+        "./ignored.foo
+         ./one/two
+         ./one/two/C.Foo2
+         ./one/two/c.foo
+         ./one/two/three
+         ./one/two/three/d.foo
+         ./one/two/three/directory_foo",
     );
-}
-
-#[test]
-fn test_max_depth() {
-    let env = TestEnv::new();
-
     env.assert_output(
         true,
-        &["--max-depth", "3"],
+        &["--no-ignore", ".", "ignored", "and", "name", "*.foo"],
+        "./ignored.foo
+         ./one/two/c.foo
+         ./one/two/three/d.foo",
+    );
+    env.assert_output(
+        true,
+        &[
+            "--no-ignore",
+            "--ignore-file",
+            "patterns",
+            ".",
+            "ignored",
+            "and",
+            "name",
+            "*.foo",
+        ],
         "./a.foo
-         ./α β
-         ./one
-         ./one.two
+         ./ignored.foo
          ./one/b.foo
+         ./one/two/c.foo
+         ./one/two/three/d.foo",
+    );
+    env.assert_output(
+        true,
+        &[
+            "--no-ignore",
+            "--ignore-file",
+            root.join("patterns").to_str().unwrap(),
+            "one",
+            "ignored",
+            "and",
+            "name",
+            "b.foo",
+        ],
+        "./one/b.foo",
+    );
+    env.assert_output(
+        true,
+        &["--all", ".", "hidden", "print"],
+        "./.hidden.foo
+         ./.ignore
+         ./one/.ffignore",
+    );
+    env.assert_output(
+        true,
+        &[
+            "--all",
+            "--no-ignore",
+            ".",
+            "(",
+            "hidden",
+            "or",
+            "ignored",
+            ")",
+            "print",
+        ],
+        "./.hidden.foo
+         ./.ignore
+         ./ignored.foo
+         ./one/.ffignore
          ./one/two
          ./one/two/C.Foo2
          ./one/two/c.foo
          ./one/two/three
-         ./symlink
-         ./symlink2",
+         ./one/two/three/d.foo
+         ./one/two/three/directory_foo",
     );
+}
+
+/// Predicates of Git status (tracked, untracked, modified, staged)
+#[test]
+fn test_git_status() {
+    let env = TestEnv::new();
+    let root = env.test_root();
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args(&["-c", "user.name=ff", "-c", "user.email=ff@localhost"])
+            .args(args)
+            .current_dir(&root)
+            .output()
+            .expect("git")
+            .status;
+
+        assert!(status.success(), "git {:?}", args);
+    };
+
+    git(&["init", "--quiet", "."]);
+    git(&["add", "a.foo", "one/b.foo", "one/two/c.foo"]);
+    git(&["commit", "--quiet", "--message", "init"]);
+    fs::write(root.join("one/b.foo"), "modified").unwrap();
+    fs::write(root.join("one/two/c.foo"), "staged").unwrap();
+    git(&["add", "one/two/c.foo", "one/two/C.Foo2"]);
 
     env.assert_output(
         true,
-        &["--max-depth", "2"],
+        &[".", "tracked", "print"],
         "./a.foo
-         ./α β
          ./one
-         ./one.two
          ./one/b.foo
          ./one/two
-         ./symlink
-         ./symlink2",
+         ./one/two/C.Foo2
+         ./one/two/c.foo",
     );
-
     env.assert_output(
         true,
-        &["--max-depth", "1"],
-        "./a.foo
-         ./α β
-         ./one
-         ./one.two
-         ./symlink
-         ./symlink2",
+        &[".", "untracked", "and", "name", "*.foo"],
+        "./one/two/three/d.foo",
+    );
+    env.assert_output(true, &[".", "modified", "print"], "./one/b.foo");
+    env.assert_output(
+        true,
+        &[".", "staged", "print"],
+        "./one/two/C.Foo2
+         ./one/two/c.foo",
     );
 
-    env.assert_output(true, &["--max-depth", "0"], "");
+    // objects in packs and nested repositories
+    git(&["gc", "--quiet"]);
+    git(&["commit", "--quiet", "--message", "next"]);
+    fs::write(root.join("one/two/C.Foo2"), "staged").unwrap();
+    git(&["add", "one/two/C.Foo2"]);
+    fs::write(root.join("one/two/C.Foo2"), "modified").unwrap();
+    git(&["-C", "one/two/three", "init", "--quiet", "."]);
+    git(&["-C", "one/two/three", "add", "d.foo"]);
+
+    env.assert_output(
+        true,
+        &[".", "modified", "or", "staged"],
+        "./one/b.foo
+         ./one/two/C.Foo2
+         ./one/two/three/d.foo",
+    );
+    env.assert_output(
+        true,
+        &[".", "staged", "and", "not", "modified"],
+        "./one/two/three/d.foo",
+    );
 }
 
+/// Predicates of file contents (contains, grep)
 #[test]
-fn test_absolute_path() {
+fn test_content_search() {
     let env = TestEnv::new();
+    let root = env.test_root();
 
-    let abs_path = get_test_root(&env);
+    fs::write(root.join("a.foo"), "fn main() {\n    // TODO\n}\n").unwrap();
+    fs::write(root.join("one/b.foo"), "todo\nTO\nDO\n").unwrap();
+    fs::write(root.join("one/two/c.foo"), b"\x00\xffTODO\x00").unwrap();
 
     env.assert_output(
         true,
-        &["--absolute-path"],
-        &format!(
-            "{abs_path}/a.foo
-             {abs_path}/α β
-             {abs_path}/one
-             {abs_path}/one.two
-             {abs_path}/one/b.foo
-             {abs_path}/one/two
-             {abs_path}/one/two/C.Foo2
-             {abs_path}/one/two/c.foo
-             {abs_path}/one/two/three
-             {abs_path}/one/two/three/d.foo
-             {abs_path}/one/two/three/directory_foo
-             {abs_path}/symlink
-             {abs_path}/symlink2",
-            abs_path = abs_path
-        ),
+        &[".", "contains", "TODO"],
+        "./a.foo
+         ./one/two/c.foo",
     );
-
+    env.assert_output(true, &[".", "contains", "TO\nDO"], "./one/b.foo");
+    env.assert_output(true, &[".", "grep", "(?i)^todo$|^TO$"], "./one/b.foo");
     env.assert_output(
         true,
-        &["--regex", ".", "foo", "--absolute-path"],
-        &format!(
-            "{abs_path}/a.foo
-             {abs_path}/one/b.foo
-             {abs_path}/one/two/c.foo
-             {abs_path}/one/two/three/d.foo
-             {abs_path}/one/two/three/directory_foo",
-            abs_path = abs_path
-        ),
+        &[".", "name", "*.foo", "and", "not", "grep", "^ +//"],
+        "./one/b.foo
+         ./one/two/c.foo
+         ./one/two/three/d.foo",
     );
-
     env.assert_output(
         true,
-        &["--regex", &abs_path, "foo"],
-        &format!(
-            "{abs_path}/a.foo
-             {abs_path}/one/b.foo
-             {abs_path}/one/two/c.foo
-             {abs_path}/one/two/three/d.foo
-             {abs_path}/one/two/three/directory_foo",
-            abs_path = abs_path
-        ),
+        &["--follow", ".", "grep", r"\xffTODO"],
+        "./one/two/c.foo\n./symlink/c.foo",
     );
 }
 
+/// Predicates of text and binary files (text, binary)
 #[test]
-fn test_sort_path() {
+fn test_text_binary() {
     let env = TestEnv::new();
+    let root = env.test_root();
 
+    fs::write(root.join("a.foo"), "#!/bin/sh\necho café\n").unwrap();
+    fs::write(root.join("one/b.foo"), b"header\x00\x01\x02\x03").unwrap();
+    fs::write(root.join("one/two/c.foo"), b"\xFE\xFF\x00t\x00x\x00t").unwrap();
+
+    env.assert_output(true, &[".", "binary", "print"], "./one/b.foo");
     env.assert_output(
-        false,
-        &["--sort-path"],
+        true,
+        &[".", "text", "and", "name", "*.foo"],
         "./a.foo
-         ./one
-         ./one/b.foo
-         ./one/two
-         ./one/two/C.Foo2
          ./one/two/c.foo
-         ./one/two/three
-         ./one/two/three/d.foo
-         ./one/two/three/directory_foo
-         ./one.two
-         ./symlink
-         ./symlink2
-         ./α β",
+         ./one/two/three/d.foo",
     );
+    env.assert_output(true, &["--sniff-size", "6", ".", "binary", "print"], "");
 }
 
+/// Predicate of MIME types (mime)
 #[test]
-fn test_type() {
+fn test_mime() {
     let env = TestEnv::new();
-
+    let root = env.test_root();
+
+    fs::write(root.join("a.foo"), "%PDF-1.4\n").unwrap();
+    fs::write(
+        root.join("one/b.foo"),
+        b"\x89PNG\r\n\x1A\n\x00\x00\x00\x0DIHDR",
+    )
+    .unwrap();
+    fs::write(
+        root.join("one/two/c.foo"),
+        b"\xFF\xD8\xFF\xE0\x00\x10JFIF\x00",
+    )
+    .unwrap();
+    fs::write(root.join("one/two/C.Foo2"), b"\x00\x01\x02\x03").unwrap();
+    fs::write(root.join("one/two/three/d.foo"), "plain\n").unwrap();
+    fs::write(root.join("one.two/e.rs"), "fn main() {}\n").unwrap();
+    fs::write(root.join("one.two/f.png"), "not an image\n").unwrap();
+
+    env.assert_output(
+        true,
+        &[".", "mime", "image/*"],
+        "./one/b.foo
+         ./one/two/c.foo",
+    );
+    env.assert_output(true, &[".", "mime", "APPLICATION/PDF"], "./a.foo");
     env.assert_output(
         true,
-        &["--type", "f"],
-        "./a.foo
-         ./α β
-         ./one/b.foo
-         ./one/two/C.Foo2
-         ./one/two/c.foo
+        &[".", "mime", "application/octet-stream"],
+        "./one/two/C.Foo2",
+    );
+    env.assert_output(
+        true,
+        &[".", "mime", "text/*"],
+        "./one.two/e.rs
+         ./one.two/f.png
          ./one/two/three/d.foo",
     );
+    env.assert_output(true, &[".", "mime", "text/x-rust"], "./one.two/e.rs");
+    env.assert_output(true, &[".", "mime", "inode/x-empty"], "./α β");
+}
+
+/// Predicate of script interpreters (interpreter)
+#[test]
+fn test_interpreter() {
+    let env = TestEnv::new();
+    let root = env.test_root();
+
+    fs::write(root.join("a.foo"), "#!/usr/bin/env python3\nprint()\n").unwrap();
+    fs::write(root.join("one/b.foo"), "#!/bin/sh -e\n").unwrap();
+    fs::write(root.join("one/two/c.foo"), "#!/usr/bin/python2.7\n").unwrap();
+    fs::write(root.join("one/two/three/d.foo"), "# !/bin/sh\n").unwrap();
 
     env.assert_output(
         true,
-        &["--type", "d"],
-        "./one
-         ./one.two
-         ./one/two
-         ./one/two/three
-         ./one/two/three/directory_foo",
+        &[".", "interpreter", "python*"],
+        "./a.foo
+         ./one/two/c.foo",
     );
-
     env.assert_output(
         true,
-        &["--type", "l"],
-        "./symlink
-         ./symlink2",
+        &[".", "type", "x", "and", "interpreter", "python*"],
+        "./a.foo",
     );
+    env.assert_output(true, &[".", "interpreter", "sh"], "./one/b.foo");
+}
 
-    env.assert_output(true, &["--type", "x"], "./a.foo");
+/// Predicates of symlink targets (lname, ilname, target-exists, target-inside)
+#[test]
+fn test_symlink_targets() {
+    let env = TestEnv::new();
+    let root = env.test_root();
 
+    symlink("../a.foo", root.join("one/rel")).unwrap();
+    symlink("/opt/old/bin", root.join("one/old")).unwrap();
+    symlink("../..", root.join("one/up")).unwrap();
+
+    env.assert_output(true, &[".", "lname", "*one/two"], "./symlink");
+    env.assert_output(true, &[".", "lname", "/opt/*"], "./one/old");
+    env.assert_output(true, &[".", "ilname", "/OPT/*"], "./one/old");
     env.assert_output(
         true,
-        &["--type", "x,l"],
-        "./a.foo
+        &[".", "target-exists", "print"],
+        "./one/rel
+         ./one/up
+         ./symlink",
+    );
+    env.assert_output(
+        true,
+        &[".", "type", "l", "and", "not", "target-exists"],
+        "./one/old
+         ./symlink2",
+    );
+    env.assert_output(
+        true,
+        &[".", "target-inside", "print"],
+        "./one/rel
          ./symlink
          ./symlink2",
     );
+    env.assert_output(
+        true,
+        &[".", "type", "l", "and", "not", "target-inside"],
+        "./one/old
+         ./one/up",
+    );
+    env.assert_output_subdirectory(true, "one", &[".", "type", "l", "and", "target-inside"], "");
 }
 
+/// Predicates of hard links and inodes (links, inum, samefile)
 #[test]
-fn test_symlink() {
+fn test_hard_links() {
+    let env = TestEnv::new();
+    let root = env.test_root();
+
+    fs::hard_link(root.join("a.foo"), root.join("one/a.bar")).unwrap();
+    fs::hard_link(root.join("a.foo"), root.join("one/two/a.bar")).unwrap();
+
+    let inode = fs::metadata(root.join("a.foo")).unwrap().ino().to_string();
+
+    env.assert_output(
+        true,
+        &[".", "type", "f", "and", "links", "+1"],
+        "./a.foo
+         ./one/a.bar
+         ./one/two/a.bar",
+    );
+    env.assert_output(
+        true,
+        &[".", "type", "f", "and", "links", "3"],
+        "./a.foo
+         ./one/a.bar
+         ./one/two/a.bar",
+    );
+    env.assert_output(
+        true,
+        &[
+            "--", ".", "type", "f", "and", "links", "-2", "and", "name", "*.foo",
+        ],
+        "./one/b.foo
+         ./one/two/c.foo
+         ./one/two/three/d.foo",
+    );
+    env.assert_output(
+        true,
+        &[".", "inum", &inode],
+        "./a.foo
+         ./one/a.bar
+         ./one/two/a.bar",
+    );
+    env.assert_output(
+        true,
+        &[".", "samefile", "one/two/a.bar"],
+        "./a.foo
+         ./one/a.bar
+         ./one/two/a.bar",
+    );
+    env.assert_output(true, &[".", "samefile", "symlink"], "./symlink");
+    env.assert_output(
+        true,
+        &["--follow", ".", "samefile", "symlink"],
+        "./one/two
+         ./symlink",
+    );
+}
+
+/// Predicates of extensions and directories (ext, iext, dir)
+#[test]
+fn test_ext_dir() {
+    let env = TestEnv::new();
+    let root = env.test_root();
+
+    fs::File::create(root.join("one/backup.tar.gz")).unwrap();
+    fs::File::create(root.join("one/two/.foo")).unwrap();
+
+    env.assert_output(
+        true,
+        &[".", "ext", "foo,tar.gz"],
+        "./a.foo
+         ./one/b.foo
+         ./one/backup.tar.gz
+         ./one/two/c.foo
+         ./one/two/three/d.foo",
+    );
+    env.assert_output(
+        true,
+        &[".", "iext", ".FOO"],
+        "./a.foo
+         ./one/b.foo
+         ./one/two/c.foo
+         ./one/two/three/d.foo",
+    );
+    env.assert_output(true, &[".", "ext", "gz"], "./one/backup.tar.gz");
+    env.assert_output(
+        true,
+        &[".", "dir", "**/t*"],
+        "./one/two
+         ./one/two/three",
+    );
+    env.assert_output(
+        true,
+        &[".", "dir", "./one*"],
+        "./one
+         ./one.two",
+    );
+}
+
+/// Predicate of empty files and directories (empty)
+#[test]
+fn test_empty() {
+    let env = TestEnv::new();
+    let root = env.test_root();
+
+    fs::write(root.join("a.foo"), "a").unwrap();
+    fs::create_dir(root.join("hidden")).unwrap();
+    fs::File::create(root.join("hidden/.foo")).unwrap();
+    fs::create_dir(root.join("ignored")).unwrap();
+    fs::File::create(root.join("ignored/ignored.foo")).unwrap();
+    fs::create_dir(root.join("listed")).unwrap();
+    fs::File::create(root.join("listed/only.bar")).unwrap();
+    fs::write(root.join("patterns"), "/listed/only.bar\n").unwrap();
+
+    env.assert_output(
+        true,
+        &[".", "empty", "and", "name", "*.foo"],
+        "./one/b.foo
+         ./one/two/c.foo
+         ./one/two/three/d.foo",
+    );
+    env.assert_output(
+        true,
+        &[".", "type", "d", "and", "empty"],
+        "./hidden
+         ./ignored
+         ./one.two
+         ./one/two/three/directory_foo",
+    );
+    env.assert_output(
+        true,
+        &["--all", ".", "type", "d", "and", "empty"],
+        "./ignored
+         ./one.two
+         ./one/two/three/directory_foo",
+    );
+    env.assert_output(
+        true,
+        &["--no-ignore", ".", "type", "d", "and", "empty"],
+        "./hidden
+         ./one.two
+         ./one/two/three/directory_foo",
+    );
+    env.assert_output(
+        true,
+        &[
+            "--ignore-file",
+            "patterns",
+            ".",
+            "type",
+            "d",
+            "and",
+            "empty",
+        ],
+        "./hidden
+         ./ignored
+         ./listed
+         ./one.two
+         ./one/two/three/directory_foo",
+    );
+    env.assert_output(
+        true,
+        &["--follow", ".", "empty", "and", "name", "symlink*"],
+        "",
+    );
+}
+
+#[test]
+fn test_follow() {
+    let env = TestEnv::new();
+
+    env.assert_output(
+        true,
+        &["--follow"],
+        "./a.foo
+         ./α β
+         ./one
+         ./one.two
+         ./one/b.foo
+         ./one/two
+         ./one/two/C.Foo2
+         ./one/two/c.foo
+         ./one/two/three
+         ./one/two/three/d.foo
+         ./one/two/three/directory_foo
+         ./symlink
+         ./symlink/C.Foo2
+         ./symlink/c.foo
+         ./symlink/three
+         ./symlink/three/d.foo
+         ./symlink/three/directory_foo
+         ./symlink2",
+    );
+
+    env.assert_output(
+        true,
+        &["--regex", ".", "c.foo", "--follow"],
+        "./one/two/c.foo
+         ./symlink/c.foo",
+    );
+}
+
+#[test]
+fn test_print0() {
+    let env = TestEnv::new();
+
+    env.assert_output(
+        true,
+        &["--regex", ".", "foo", "--print0"],
+        "./a.fooNULL
+         ./one/b.fooNULL
+         ./one/two/c.fooNULL
+         ./one/two/three/d.fooNULL
+         ./one/two/three/directory_fooNULL",
+    );
+}
+
+#[test]
+fn test_max_depth() {
+    let env = TestEnv::new();
+
+    env.assert_output(
+        true,
+        &["--max-depth", "3"],
+        "./a.foo
+         ./α β
+         ./one
+         ./one.two
+         ./one/b.foo
+         ./one/two
+         ./one/two/C.Foo2
+         ./one/two/c.foo
+         ./one/two/three
+         ./symlink
+         ./symlink2",
+    );
+
+    env.assert_output(
+        true,
+        &["--max-depth", "2"],
+        "./a.foo
+         ./α β
+         ./one
+         ./one.two
+         ./one/b.foo
+         ./one/two
+         ./symlink
+         ./symlink2",
+    );
+
+    env.assert_output(
+        true,
+        &["--max-depth", "1"],
+        "./a.foo
+         ./α β
+         ./one
+         ./one.two
+         ./symlink
+         ./symlink2",
+    );
+
+    env.assert_output(true, &["--max-depth", "0"], "");
+}
+
+#[test]
+fn test_absolute_path() {
+    let env = TestEnv::new();
+
+    let abs_path = get_test_root(&env);
+
+    env.assert_output(
+        true,
+        &["--absolute-path"],
+        &format!(
+            "{abs_path}/a.foo
+             {abs_path}/α β
+             {abs_path}/one
+             {abs_path}/one.two
+             {abs_path}/one/b.foo
+             {abs_path}/one/two
+             {abs_path}/one/two/C.Foo2
+             {abs_path}/one/two/c.foo
+             {abs_path}/one/two/three
+             {abs_path}/one/two/three/d.foo
+             {abs_path}/one/two/three/directory_foo
+             {abs_path}/symlink
+             {abs_path}/symlink2",
+            abs_path = abs_path
+        ),
+    );
+
+    env.assert_output(
+        true,
+        &["--regex", ".", "foo", "--absolute-path"],
+        &format!(
+            "{abs_path}/a.foo
+             {abs_path}/one/b.foo
+             {abs_path}/one/two/c.foo
+             {abs_path}/one/two/three/d.foo
+             {abs_path}/one/two/three/directory_foo",
+            abs_path = abs_path
+        ),
+    );
+
+    env.assert_output(
+        true,
+        &["--regex", &abs_path, "foo"],
+        &format!(
+            "{abs_path}/a.foo
+             {abs_path}/one/b.foo
+             {abs_path}/one/two/c.foo
+             {abs_path}/one/two/three/d.foo
+             {abs_path}/one/two/three/directory_foo",
+            abs_path = abs_path
+        ),
+    );
+}
+
+#[test]
+fn test_sort_path() {
+    let env = TestEnv::new();
+
+    env.assert_output(
+        false,
+        &["--sort-path"],
+        "./a.foo
+         ./one
+         ./one/b.foo
+         ./one/two
+         ./one/two/C.Foo2
+         ./one/two/c.foo
+         ./one/two/three
+         ./one/two/three/d.foo
+         ./one/two/three/directory_foo
+         ./one.two
+         ./symlink
+         ./symlink2
+         ./α β",
+    );
+}
+
+#[test]
+fn test_type() {
+    let env = TestEnv::new();
+
+    env.assert_output(
+        true,
+        &["--type", "f"],
+        "./a.foo
+         ./α β
+         ./one/b.foo
+         ./one/two/C.Foo2
+         ./one/two/c.foo
+         ./one/two/three/d.foo",
+    );
+
+    env.assert_output(
+        true,
+        &["--type", "d"],
+        "./one
+         ./one.two
+         ./one/two
+         ./one/two/three
+         ./one/two/three/directory_foo",
+    );
+
+    env.assert_output(
+        true,
+        &["--type", "l"],
+        "./symlink
+         ./symlink2",
+    );
+
+    env.assert_output(true, &["--type", "x"], "./a.foo");
+
+    env.assert_output(
+        true,
+        &["--type", "x,l"],
+        "./a.foo
+         ./symlink
+         ./symlink2",
+    );
+}
+
+#[test]
+fn test_symlink() {
     let env = TestEnv::new();
 
     let abs_path = get_test_root(&env);
@@ -988,21 +1546,108 @@ fn test_exclude_dirs() {
 
     env.assert_output(
         true,
-        &["--exclude", &format!("{}/one/two", abs_path)],
+        &["--exclude", &format!("{}/one/two", abs_path)],
+        "./a.foo
+         ./α β
+         ./one
+         ./one.two
+         ./one/b.foo
+         ./one/two
+         ./one/two/C.Foo2
+         ./one/two/c.foo
+         ./one/two/three
+         ./one/two/three/d.foo
+         ./one/two/three/directory_foo
+         ./symlink
+         ./symlink2",
+    );
+}
+
+#[test]
+fn test_exclude_patterns() {
+    let env = TestEnv::new();
+
+    env.assert_output(
+        true,
+        &["--exclude-glob", "**/two", "--exclude-glob", "symlink*"],
+        "./a.foo
+         ./α β
+         ./one
+         ./one.two
+         ./one/b.foo",
+    );
+
+    env.assert_output(
+        true,
+        &["--exclude-regex", r"\.foo$", "--exclude-regex", "^one/two/"],
+        "./α β
+         ./one
+         ./one.two
+         ./one/two
+         ./symlink
+         ./symlink2",
+    );
+
+    let abs_path = get_test_root(&env);
+
+    env.assert_output(
+        true,
+        &[
+            "--exclude-glob",
+            &format!("{}/one", abs_path),
+            "--exclude-glob",
+            "*2",
+        ],
+        "./a.foo
+         ./α β
+         ./one.two
+         ./symlink",
+    );
+}
+
+/// Path lists (--exclude-from, --files-from)
+#[test]
+fn test_path_lists() {
+    let env = TestEnv::new();
+    let root = env.test_root();
+
+    fs::write(root.join("excludes"), "one\nexcludes\nfiles\n").unwrap();
+    fs::write(root.join("files"), "a.foo\0one/two/c.foo\0one\0missing\0").unwrap();
+
+    env.assert_output(
+        true,
+        &["--exclude-from", "excludes"],
         "./a.foo
          ./α β
-         ./one
          ./one.two
-         ./one/b.foo
-         ./one/two
-         ./one/two/C.Foo2
-         ./one/two/c.foo
-         ./one/two/three
-         ./one/two/three/d.foo
-         ./one/two/three/directory_foo
          ./symlink
          ./symlink2",
     );
+    env.assert_output(
+        false,
+        &["--files-from", "files"],
+        "./a.foo
+         ./one/two/c.foo
+         ./one",
+    );
+    env.assert_output(
+        false,
+        &["--files-from", "files", ".", "type", "f", "print"],
+        "./a.foo
+         ./one/two/c.foo",
+    );
+    env.assert_output(
+        false,
+        &["--files-from", "files", "--exclude", "one/two"],
+        "./a.foo
+         ./one",
+    );
+    env.assert_output(
+        false,
+        &["--files-from", "files", "--exclude-glob", "one/*"],
+        "./a.foo
+         ./one",
+    );
 }
 
 #[test]
@@ -1249,3 +1894,516 @@ fn test_filter_chain() {
     // hard to test due to multi-threading
     env.assert_output(true, &[".", "quit", "or", "print"], "");
 }
+
+#[test]
+fn test_json() {
+    let env = TestEnv::new();
+
+    let output = env.get_output(&["--json", "--sort-path", ".", "name", "*.foo"]);
+    let lines = output.lines().collect::<Vec<_>>();
+
+    assert_eq!(lines.len(), 4, "{}", output);
+    assert!(lines[0].starts_with(r#"{"path":"./a.foo","type":"file","size":0,"mode":"#));
+    assert!(lines[1].starts_with(r#"{"path":"./one/b.foo","type":"file""#));
+    assert!(lines[1].ends_with(r#","depth":2,"target":null,"root":"."}"#));
+
+    let output = env.get_output(&["--json", ".", "name", "symlink"]);
+
+    assert!(output.contains(r#""type":"symlink""#), "{}", output);
+    assert!(output.contains(r#""depth":1,"target":""#), "{}", output);
+    assert!(output.ends_with("}\n"), "{}", output);
+
+    let output = env.get_output(&["--json", "--print0", "one/two/three", "type", "d"]);
+
+    assert!(
+        output.starts_with(r#"{"path":"./one/two/three/directory_foo","type":"directory""#),
+        "{}",
+        output
+    );
+    assert!(
+        output.ends_with("\"root\":\"./one/two/three\"}\0"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn test_output_format() {
+    let env = TestEnv::new();
+
+    env.assert_output(
+        false,
+        &[
+            "--output-format",
+            "csv",
+            "--sort-path",
+            ".",
+            "name",
+            "*.foo",
+        ],
+        "path
+         ./a.foo
+         ./one/b.foo
+         ./one/two/c.foo
+         ./one/two/three/d.foo",
+    );
+
+    env.assert_output(
+        false,
+        &[
+            "--output-format=csv",
+            "--columns=name,type,size,depth",
+            "--sort-path",
+            ".",
+            "name",
+            "*.foo",
+        ],
+        "name,type,size,depth
+         a.foo,file,0,1
+         b.foo,file,0,2
+         c.foo,file,0,3
+         d.foo,file,0,4",
+    );
+
+    env.assert_output(
+        false,
+        &[
+            "--output-format=tsv",
+            "--columns=path,root,type",
+            "--sort-path",
+            "one/two",
+            "type",
+            "d",
+        ],
+        "path	root	type
+         ./one/two/three	./one/two	directory
+         ./one/two/three/directory_foo	./one/two	directory",
+    );
+
+    env.assert_output(
+        false,
+        &["--output-format", "csv", "--print0", ".", "name", "a.foo"],
+        "pathNULL
+         ./a.fooNULL",
+    );
+
+    let output = env.get_output(&[
+        "--output-format=csv",
+        "--columns=mode,mtime",
+        ".",
+        "name",
+        "a.foo",
+    ]);
+    let lines = output.lines().collect::<Vec<_>>();
+
+    assert_eq!(lines[0], "mode,mtime");
+    assert!(lines[1].starts_with("07"), "{}", output);
+    assert!(lines[1].ends_with("Z"), "{}", output);
+}
+
+#[test]
+fn test_long() {
+    let env = TestEnv::new();
+
+    let line =
+        r"^ *\d+ +\d+ [-dl][-rwxsStT]{9} +\d+ \S+ +\S+ +\d+ \w{3} [ \d]\d ( \d{4}|\d\d:\d\d) ";
+    let output = env.get_output(&["--long", "--sort-path", ".", "name", "*.foo"]);
+    let lines = output.lines().collect::<Vec<_>>();
+    let regex = regex::Regex::new(&format!("{}{}$", line, escape("./a.foo"))).unwrap();
+
+    assert_eq!(lines.len(), 4, "{}", output);
+    assert!(regex.is_match(lines[0]), "{}", output);
+    assert!(lines[0].contains(" -rwx"), "{}", output);
+    assert!(lines[1].ends_with(" ./one/b.foo"), "{}", output);
+
+    let output = env.get_output(&["-l", ".", "name", "symlink"]);
+    let regex = regex::Regex::new(&format!("{}{} -> .*/one/two\n$", line, escape("./symlink")));
+
+    assert!(regex.unwrap().is_match(&output), "{}", output);
+    assert!(output.contains(" lrwxrwxrwx "), "{}", output);
+
+    let output = env.get_output(&[".", "name", "a.foo", "ls", "print"]);
+    let lines = output.lines().collect::<Vec<_>>();
+
+    assert_eq!(lines.len(), 2, "{}", output);
+    assert!(lines[0].ends_with(" ./a.foo"), "{}", output);
+    assert_eq!(lines[1], "./a.foo");
+
+    let output = env.get_output(&["--long", ".", "name", "a.foo", "print0", "ls"]);
+
+    let lines = output.split('\0').collect::<Vec<_>>();
+
+    assert_eq!(lines.len(), 2, "{:?}", output);
+    assert!(lines[0].ends_with(" ./a.foo"), "{:?}", output);
+    assert!(lines[1].ends_with(" ./a.foo\n"), "{:?}", output);
+}
+
+/// Tree view of the search results (--tree)
+#[test]
+fn test_tree() {
+    let env = TestEnv::new();
+
+    assert_eq!(
+        env.get_output(&["--tree=ascii", ".", "name", "*.foo"]),
+        ".
+|-- a.foo
+`-- one
+    |-- b.foo
+    `-- two
+        |-- c.foo
+        `-- three
+            `-- d.foo
+"
+    );
+
+    assert_eq!(
+        env.get_output(&["--tree", "one/two", "name", "*.foo"]),
+        "./one/two
+├── c.foo
+└── three
+    └── d.foo
+"
+    );
+}
+
+/// Count and summary mode (--count, --stats)
+#[test]
+fn test_count_stats() {
+    let env = TestEnv::new();
+
+    assert_eq!(env.get_output(&["--count", ".", "name", "*.foo"]), "4\n");
+    assert_eq!(
+        env.get_output(&["--count", "-0", "one", "name", "*.foo"]),
+        "3\0"
+    );
+    assert_eq!(
+        env.get_output(&["--count", ".", "name", "one", "prune", "or", "print"]),
+        env.get_output(&["--count", "-E", "one"])
+    );
+
+    let (stdout, stderr) = env.get_outputs(&["--stats", "-E", "one/two", "one"]);
+    let lines = stderr.lines().collect::<Vec<_>>();
+
+    assert_eq!(stdout, "./one/b.foo\n");
+    assert_eq!(
+        lines[0..5],
+        [
+            "matches: 1",
+            "directories: 1",
+            "skipped: 1",
+            "errors: 0",
+            "bytes: 0"
+        ]
+    );
+    assert!(lines[5].starts_with("elapsed: "), "{}", stderr);
+
+    // .hidden.foo, .ignore and ignored.foo
+    let (_, stderr) = env.get_outputs(&["--stats", "."]);
+
+    assert_eq!(stderr.lines().nth(2), Some("skipped: 3"), "{}", stderr);
+
+    let (_, stderr) = env.get_outputs(&["--stats", "--all", "--no-ignore", "."]);
+
+    assert_eq!(stderr.lines().nth(2), Some("skipped: 0"), "{}", stderr);
+}
+
+/// Limit the number of search results (--max-results, -1)
+#[test]
+fn test_max_results() {
+    let env = TestEnv::new();
+
+    let output = env.get_output(&["--max-results=2", ".", "name", "*.foo"]);
+
+    assert_eq!(output.lines().count(), 2, "{}", output);
+    assert_eq!(
+        env.get_output(&["-1", ".", "name", "*.foo"])
+            .lines()
+            .count(),
+        1
+    );
+    assert_eq!(env.get_output(&["--max-results=0"]), "");
+    assert_eq!(
+        env.get_output(&["--max-results=9", "one", "name", "*.foo"])
+            .lines()
+            .count(),
+        3
+    );
+
+    env.assert_output(
+        false,
+        &["--max-results=2", "--sort-path", ".", "name", "*.foo"],
+        "./a.foo
+         ./one/b.foo",
+    );
+    env.assert_output(
+        false,
+        &[
+            "-1",
+            "--sort-path",
+            ".",
+            "name",
+            "one",
+            "prune",
+            "or",
+            "name",
+            "*.foo",
+            "print",
+        ],
+        "./a.foo",
+    );
+    env.assert_output(
+        false,
+        &[
+            "--max-results=1",
+            "-1",
+            "--sort-path",
+            "--",
+            "one",
+            "name",
+            "*.foo",
+        ],
+        "./one/b.foo",
+    );
+}
+
+/// Sort keys (--sort, --reverse)
+#[test]
+fn test_sort() {
+    let env = TestEnv::new();
+    let root = env.test_root();
+
+    fs::write(root.join("one/two/c.foo"), "12345").unwrap();
+    fs::write(root.join("one/b.foo"), "123").unwrap();
+    fs::File::create(root.join("one/b10.foo")).unwrap();
+    fs::File::create(root.join("one/b9.foo")).unwrap();
+
+    env.assert_output(
+        false,
+        &["--sort=size,path", ".", "name", "*.foo"],
+        "./a.foo
+         ./one/b10.foo
+         ./one/b9.foo
+         ./one/two/three/d.foo
+         ./one/b.foo
+         ./one/two/c.foo",
+    );
+    env.assert_output(
+        false,
+        &["--sort", "version", "one", "name", "b*"],
+        "./one/b.foo
+         ./one/b9.foo
+         ./one/b10.foo",
+    );
+    env.assert_output(
+        false,
+        &["--sort=depth", "--reverse", ".", "name", "*.foo"],
+        "./one/two/three/d.foo
+         ./one/two/c.foo
+         ./one/b9.foo
+         ./one/b10.foo
+         ./one/b.foo
+         ./a.foo",
+    );
+    env.assert_output(
+        false,
+        &["--reverse", "one/two", "type", "f"],
+        "./one/two/three/d.foo
+         ./one/two/c.foo
+         ./one/two/C.Foo2",
+    );
+    env.assert_output(
+        false,
+        &["--sort=ext,name", "one/two", "type", "f"],
+        "./one/two/C.Foo2
+         ./one/two/c.foo
+         ./one/two/three/d.foo",
+    );
+}
+
+/// Spill sorted search results to temporary files (--sort-memory)
+#[test]
+fn test_sort_memory() {
+    let env = TestEnv::new();
+
+    for args in &[
+        vec!["--sort-path", "--all"],
+        vec!["--sort=name,depth", "--reverse"],
+        vec!["--tree", "--max-results=3"],
+    ] {
+        let mut spilled = args.clone();
+
+        spilled.push("--sort-memory=1");
+        assert_eq!(env.get_output(&spilled), env.get_output(args), "{:?}", args);
+    }
+}
+
+/// Streaming sorted search results (--ordered)
+#[test]
+fn test_ordered() {
+    let env = TestEnv::new();
+
+    for args in &[
+        vec!["--all"],
+        vec![".", "name", "*.foo"],
+        vec![".", "name", "two", "prune", "or", "print"],
+        vec!["-E", "one/two", "--no-ignore"],
+        vec!["--max-results=3"],
+    ] {
+        let mut sorted = args.clone();
+        let mut ordered = args.clone();
+
+        sorted.insert(0, "--sort-path");
+        ordered.insert(0, "--ordered");
+        assert_eq!(
+            env.get_output(&ordered),
+            env.get_output(&sorted),
+            "{:?}",
+            args
+        );
+    }
+
+    // the pruned directories are not walked, so the loop is never found
+    symlink("..", env.test_root().join("one/two/three/loop")).unwrap();
+
+    let (stdout, stderr) = env.get_outputs(&[
+        "--ordered",
+        "--follow",
+        "one",
+        "name",
+        "two",
+        "prune",
+        "or",
+        "name",
+        "*.foo",
+        "print",
+    ]);
+
+    assert_eq!(stdout, "./one/b.foo\n");
+    assert_eq!(stderr, "");
+}
+
+/// Output path transformations (--strip-prefix, --relative-to, --base-name)
+#[test]
+fn test_path_transform() {
+    let env = TestEnv::new();
+
+    env.assert_output(
+        true,
+        &["--strip-prefix", "one", "name", "*.foo"],
+        "one/b.foo
+         one/two/c.foo
+         one/two/three/d.foo",
+    );
+    env.assert_output(
+        true,
+        &["--base-name", ".", "name", "*.foo"],
+        "a.foo
+         b.foo
+         c.foo
+         d.foo",
+    );
+    env.assert_output(
+        true,
+        &[
+            "--relative-to",
+            "one/two",
+            "-D",
+            "one/two",
+            "one",
+            "name",
+            "[bc].foo",
+        ],
+        "../b.foo
+         c.foo
+         c.foo",
+    );
+    env.assert_output(
+        true,
+        &["--relative-to=one", "one/two/three"],
+        "two/three/d.foo
+         two/three/directory_foo",
+    );
+    env.assert_output(
+        false,
+        &[
+            "--strip-prefix",
+            "--columns=path,name",
+            "--output-format=csv",
+            "-S",
+            "one/two/three",
+        ],
+        "path,name
+         one/two/three/d.foo,d.foo
+         one/two/three/directory_foo,directory_foo",
+    );
+}
+
+/// Real paths and logical paths (--real-path, --logical-path)
+#[test]
+fn test_real_path() {
+    let env = TestEnv::new();
+
+    let real_path = get_test_root(&env);
+    let abs_path = env.test_root();
+    let abs_path = abs_path.to_str().unwrap();
+
+    env.assert_output(
+        true,
+        &["--real-path", "--follow", "symlink", "name", "*.foo"],
+        &format!(
+            "{real_path}/one/two/c.foo
+             {real_path}/one/two/three/d.foo",
+            real_path = real_path
+        ),
+    );
+    env.assert_output(
+        true,
+        &["--real-path", ".", "name", "symlink"],
+        &format!("{real_path}/symlink", real_path = real_path),
+    );
+    env.assert_output(
+        true,
+        &["--real-path", "--follow", "-E", "one/two/three", "symlink"],
+        &format!(
+            "{real_path}/one/two/C.Foo2
+             {real_path}/one/two/c.foo",
+            real_path = real_path
+        ),
+    );
+    env.assert_output(
+        true,
+        &["--logical-path", "one/two/../two", "name", "*.foo"],
+        &format!(
+            "{abs_path}/one/two/c.foo
+             {abs_path}/one/two/three/d.foo",
+            abs_path = abs_path
+        ),
+    );
+}
+
+/// Deduplicate search results (--unique)
+#[test]
+fn test_unique() {
+    let env = TestEnv::new();
+
+    env.assert_output(
+        true,
+        &[
+            "--unique", "-D", "one", "-D", "one/two", "one", "name", "*.foo",
+        ],
+        "./one/b.foo
+         ./one/two/c.foo
+         ./one/two/three/d.foo",
+    );
+    env.assert_output(
+        false,
+        &["--unique", "--ordered", "--follow", ".", "name", "c.foo"],
+        "./one/two/c.foo",
+    );
+    env.assert_output(
+        true,
+        &["--follow", ".", "name", "c.foo"],
+        "./one/two/c.foo
+         ./symlink/c.foo",
+    );
+}